@@ -0,0 +1,61 @@
+use crate::models::{BonusRule, BonusShape, Coord, GameState, BONUS_RULES, BONUS_SHAPES};
+use crate::triggers::get_item_cells;
+use crate::utils::get_game_random_int;
+
+pub fn get_rule(difficulty: u16) -> &'static BonusRule {
+    BONUS_RULES
+        .iter()
+        .find(|rule| rule.difficulty == difficulty)
+        .unwrap_or(&BONUS_RULES[BONUS_RULES.len() - 1])
+}
+
+// Starts from a random shape and falls back to smaller ones until something fits.
+pub fn spawn_bonus(game_state: &mut GameState, rule: &BonusRule) -> bool {
+    let first = get_game_random_int(&mut game_state.rng, 0, BONUS_SHAPES.len());
+
+    for shape in &BONUS_SHAPES[first..] {
+        let positions = get_free_positions(game_state, shape);
+
+        if positions.is_empty() { continue; }
+
+        let (x, y) = positions[get_game_random_int(&mut game_state.rng, 0, positions.len())];
+
+        game_state.bonus_cells = shape.cells.iter().map(|(i, j)| (x + i, y + j)).collect();
+        game_state.bonus_value = rule.value * shape.value_percent / 100;
+
+        return true;
+    }
+
+    false
+}
+
+pub fn update_bonus_value(game_state: &mut GameState, rule: &BonusRule) {
+    if game_state.bonus_cells.is_empty() { return; }
+
+    game_state.bonus_value = game_state.bonus_value.saturating_sub(rule.decay);
+
+    if game_state.bonus_value == 0 {
+        game_state.bonus_cells.clear();
+    }
+}
+
+pub fn is_bonus(game_state: &GameState, coord: Coord) -> bool {
+    game_state.bonus_cells.contains(&coord)
+}
+
+fn get_free_positions(game_state: &GameState, shape: &BonusShape) -> Vec<Coord> {
+    let height = shape.cells.iter().map(|(i, _)| i + 1).max().unwrap_or(1);
+    let width = shape.cells.iter().map(|(_, j)| j + 1).max().unwrap_or(1);
+    let items = get_item_cells(game_state);
+    let mut positions = Vec::new();
+
+    for x in 0..game_state.level.height.saturating_sub(height - 1) {
+        for y in 0..game_state.level.width.saturating_sub(width - 1) {
+            let is_free = shape.cells.iter().all(|(i, j)| game_state.grid[x + i][y + j] == 0 && !items.contains(&(x + i, y + j)));
+
+            if is_free { positions.push((x, y)); }
+        }
+    }
+
+    positions
+}
@@ -0,0 +1,112 @@
+use crate::food::get_food_at;
+use crate::modifiers::is_body_hidden;
+use crate::models::{Coord, GameState, Level, VisibilityEnum, CAMERA_MARGIN, FIELD_SIZE, MINIMAP_SIZE, SNAKE, WALL};
+
+// Rows and columns of the board shown at once; worlds larger than the screen
+// are seen through a window that follows the snake, with the minimap in the
+// columns to its right.
+pub fn get_view_size(level: &Level) -> (usize, usize) {
+    (level.height.min(FIELD_SIZE), level.width.min(FIELD_SIZE - get_minimap_width(level)))
+}
+
+// In board cells, two characters each; a framed minimap is at most
+// `MINIMAP_SIZE + 2` characters wide.
+pub fn get_minimap_width(level: &Level) -> usize {
+    if is_scrolling(level) { (MINIMAP_SIZE + 2).div_ceil(2) } else { 0 }
+}
+
+pub fn is_scrolling(level: &Level) -> bool {
+    level.height > FIELD_SIZE || level.width > FIELD_SIZE
+}
+
+// Called after every step. The camera only moves once the head gets within
+// `CAMERA_MARGIN` cells of the window's edge, and then just far enough to keep
+// it there, so the board scrolls one cell at a time along with the snake.
+pub fn follow_head(game_state: &mut GameState) {
+    let level = &game_state.level;
+    let head = game_state.snake_body[0];
+    let (view_height, view_width) = get_view_size(level);
+
+    game_state.camera = (
+        follow_axis(head.0, game_state.camera.0, view_height, level.height),
+        follow_axis(head.1, game_state.camera.1, view_width, level.width),
+    );
+}
+
+pub fn center_on(level: &Level, coord: Coord) -> Coord {
+    let (view_height, view_width) = get_view_size(level);
+
+    (
+        coord.0.saturating_sub(view_height / 2).min(level.height - view_height),
+        coord.1.saturating_sub(view_width / 2).min(level.width - view_width),
+    )
+}
+
+// One character per block of cells, framed, to be drawn next to the top right
+// corner of the board. Only what the mask lets through shows up.
+pub fn get_minimap_lines(game_state: &GameState, mask: Option<&[Vec<VisibilityEnum>]>) -> Vec<String> {
+    let level = &game_state.level;
+    let block_height = level.height.div_ceil(MINIMAP_SIZE);
+    let block_width = level.width.div_ceil(MINIMAP_SIZE);
+    let rows = level.height.div_ceil(block_height);
+    let cols = level.width.div_ceil(block_width);
+    let mut lines = Vec::new();
+
+    for row in 0..rows {
+        let blocks: String = (0..cols)
+            .map(|col| get_block_char(game_state, mask, (row * block_height, col * block_width), (block_height, block_width)))
+            .collect();
+
+        lines.push(format!("|{}|", blocks));
+    }
+
+    lines.push(format!("+{}+", "-".repeat(cols)));
+    lines
+}
+
+fn get_block_char(game_state: &GameState, mask: Option<&[Vec<VisibilityEnum>]>, start: Coord, size: Coord) -> char {
+    let level = &game_state.level;
+    let mut has_head = false;
+    let mut has_food = false;
+    let mut has_snake = false;
+    let mut walls = 0;
+    let mut cells = 0;
+
+    for i in start.0..(start.0 + size.0).min(level.height) {
+        for j in start.1..(start.1 + size.1).min(level.width) {
+            let visibility = mask.map(|mask| mask[i][j]).unwrap_or(VisibilityEnum::Visible);
+            let cell = game_state.grid[i][j];
+
+            cells += 1;
+
+            if visibility == VisibilityEnum::Hidden { continue; }
+            if cell == WALL { walls += 1; }
+            if visibility == VisibilityEnum::Remembered { continue; }
+
+            has_head |= game_state.snake_body[0] == (i, j);
+            has_food |= get_food_at(game_state, (i, j)).is_some();
+            has_snake |= cell == SNAKE && !is_body_hidden(game_state);
+        }
+    }
+
+    if has_head { '@' }
+        else if has_food { '*' }
+        else if has_snake { 'o' }
+        else if walls * 2 > cells { '#' }
+        else { ' ' }
+}
+
+fn follow_axis(position: usize, camera: usize, view: usize, world: usize) -> usize {
+    if view >= world { return 0; }
+
+    let margin = CAMERA_MARGIN.min((view - 1) / 2);
+    let camera = if position < camera + margin {
+        position.saturating_sub(margin)
+    } else if position + margin >= camera + view {
+        position + margin + 1 - view
+    } else {
+        camera
+    };
+
+    camera.min(world - view)
+}
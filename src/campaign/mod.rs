@@ -0,0 +1,69 @@
+use crate::models::{Campaign, CampaignStage, GameState, ScreensEnum, State, CAMPAIGN_LIVES, CAMPAIGN_STAGES};
+
+// Resumes the saved campaign at the start of its current stage, or starts a new
+// one when there is nothing to resume.
+pub fn start_campaign(state: &mut State) {
+    let campaign = state.app_state.campaign.unwrap_or(Campaign { stage: 0, lives: CAMPAIGN_LIVES, score: 0 });
+
+    start_stage(state, campaign);
+}
+
+pub fn get_stage(campaign: &Campaign) -> &'static CampaignStage {
+    &CAMPAIGN_STAGES[campaign.stage.min(CAMPAIGN_STAGES.len() - 1)]
+}
+
+pub fn is_stage_cleared(game_state: &GameState) -> bool {
+    game_state.campaign.is_some_and(|campaign| game_state.food_eaten >= get_stage(&campaign).food_target)
+}
+
+// Moves on to the next stage with the current score. Returns false when the
+// cleared stage was the last one.
+pub fn advance_stage(state: &mut State) -> bool {
+    let Some(campaign) = state.game_state.campaign else { return false; };
+
+    if campaign.stage + 1 >= CAMPAIGN_STAGES.len() { return false; }
+
+    start_stage(state, Campaign { stage: campaign.stage + 1, score: state.game_state.score, ..campaign });
+
+    true
+}
+
+// Restarts the current stage with one life less. Returns false when that was
+// the last life (or the game is not part of a campaign).
+pub fn lose_life(state: &mut State) -> bool {
+    let Some(campaign) = state.game_state.campaign else { return false; };
+
+    if campaign.lives <= 1 { return false; }
+
+    start_stage(state, Campaign { lives: campaign.lives - 1, ..campaign });
+
+    true
+}
+
+pub fn get_summary(campaign: &Campaign, game_state: &GameState) -> String {
+    format!(
+        "Stage: {}/{}   Food: {}/{}   Lives: {}",
+        campaign.stage + 1,
+        CAMPAIGN_STAGES.len(),
+        game_state.food_eaten,
+        get_stage(campaign).food_target,
+        campaign.lives,
+    )
+}
+
+fn start_stage(state: &mut State, campaign: Campaign) {
+    let app_state = &mut state.app_state;
+    let level = &app_state.levels[get_stage(&campaign).level];
+    let mut game_state = GameState::new(app_state.difficulty, level);
+
+    game_state.score = campaign.score;
+    game_state.campaign = Some(campaign);
+    game_state.freeze = true;
+
+    state.game_state = game_state;
+    app_state.campaign = Some(campaign);
+    app_state.game_started = true;
+    app_state.selected_screen = ScreensEnum::Game;
+    app_state.screen_changed = true;
+    app_state.dirty = true;
+}
@@ -0,0 +1,223 @@
+use crate::generator::generate_level;
+use crate::modes;
+use crate::modifiers::{format_tags, parse_tags};
+use crate::models::{ChallengeCode, GameModesEnum, GameState, Level, LevelSource, LevelStylesEnum, MenusEnum, ScreensEnum, State, DIFFICULTY_MENU_ITEMS, LEVEL_STYLES};
+
+const BASE36: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const MAX_CODE_LENGTH: usize = 40;
+
+// A code is the mode and difficulty, the level, the game seed and any modifier
+// tags, e.g. "T2-L3-1Z141Z3" (Time Attack on Medium, third built-in level) or
+// "C4-GM4ZK2P1-1Z141Z3-IV" (Classic on Extreme, generated maze, invisible body
+// and limited vision). Games that cannot be replayed exactly (custom levels,
+// unseeded games, puzzles, dailies) have none.
+pub fn encode(game_state: &GameState) -> Option<String> {
+    let mode = get_mode_char(game_state.mode)?;
+    let level = match game_state.level.source? {
+        LevelSource::BuiltIn(index) => format!("L{}", index + 1),
+        LevelSource::Generated { seed, style } => format!("G{}{}", get_style_char(style), to_base36(seed)),
+    };
+    let seed = to_base36(game_state.seed?);
+    let code = format!("{}{}-{}-{}", mode, game_state.difficulty, level, seed);
+
+    if game_state.modifiers.is_empty() { return Some(code); }
+
+    Some(format!("{}-{}", code, format_tags(&game_state.modifiers)))
+}
+
+pub fn decode(code: &str) -> Result<ChallengeCode, String> {
+    let code = code.trim().to_uppercase();
+    let parts: Vec<&str> = code.split('-').collect();
+    let (game, level, seed, tags) = match parts.as_slice() {
+        [game, level, seed] => (game, level, seed, ""),
+        [game, level, seed, tags] if !tags.is_empty() => (game, level, seed, *tags),
+        _ => return Err(String::from("a code looks like C2-L1-1Z141Z3")),
+    };
+
+    let mut game_chars = game.chars();
+    let mode = game_chars.next().and_then(get_mode_from_char).ok_or(format!("unknown game mode in '{}'", game))?;
+    let difficulty = game_chars.as_str().parse::<u16>().ok()
+        .filter(|difficulty| DIFFICULTY_MENU_ITEMS.iter().any(|item| item.value == *difficulty))
+        .ok_or(format!("unknown difficulty in '{}'", game))?;
+
+    let level = parse_level_source(level).ok_or(format!("unknown level '{}'", level))?;
+    let seed = from_base36(seed).ok_or(format!("invalid seed '{}'", seed))?;
+    let modifiers = parse_tags(tags).ok_or(format!("unknown modifiers '{}'", tags))?;
+
+    Ok(ChallengeCode { mode, difficulty, level, seed, modifiers })
+}
+
+pub fn play_code(state: &mut State) {
+    let app_state = &mut state.app_state;
+    let code = match decode(&app_state.code_input) {
+        Ok(code) => code,
+        Err(error) => {
+            app_state.code_error = Some(error);
+            return;
+        }
+    };
+
+    let Some(level) = get_level(&app_state.levels, code.level) else {
+        app_state.code_error = Some(String::from("the level in this code does not exist"));
+        return;
+    };
+
+    state.game_state = modes::new_game(code.difficulty, code.mode, &level, Some(code.seed));
+    state.game_state.from_code = true;
+    state.game_state.modifiers = code.modifiers;
+
+    app_state.code_input.clear();
+    app_state.code_error = None;
+    app_state.game_started = true;
+    app_state.selected_menu = MenusEnum::MainMenu;
+    app_state.selected_menu_item = 0;
+    app_state.selected_screen = ScreensEnum::Game;
+}
+
+pub fn add_code_char(input: &mut String, c: char) {
+    if input.len() < MAX_CODE_LENGTH && (c.is_ascii_alphanumeric() || c == '-') {
+        input.push(c.to_ascii_uppercase());
+    }
+}
+
+fn get_level(levels: &[Level], source: LevelSource) -> Option<Level> {
+    match source {
+        LevelSource::BuiltIn(_) => levels.iter().find(|level| level.source == Some(source)).cloned(),
+        LevelSource::Generated { seed, style } => Some(generate_level(seed, style)),
+    }
+}
+
+fn parse_level_source(text: &str) -> Option<LevelSource> {
+    let mut chars = text.chars();
+
+    match chars.next()? {
+        'L' => {
+            let number = chars.as_str().parse::<usize>().ok().filter(|number| *number > 0)?;
+
+            Some(LevelSource::BuiltIn(number - 1))
+        }
+        'G' => {
+            let style = chars.next().and_then(get_style_from_char)?;
+            let seed = from_base36(chars.as_str())?;
+
+            Some(LevelSource::Generated { seed, style })
+        }
+        _ => None,
+    }
+}
+
+fn get_mode_char(mode: GameModesEnum) -> Option<char> {
+    match mode {
+        GameModesEnum::Classic => Some('C'),
+        GameModesEnum::TimeAttack => Some('T'),
+        GameModesEnum::Survival => Some('S'),
+        GameModesEnum::Zen => Some('Z'),
+        GameModesEnum::Fog => Some('F'),
+        GameModesEnum::Puzzle | GameModesEnum::Daily => None,
+    }
+}
+
+fn get_mode_from_char(c: char) -> Option<GameModesEnum> {
+    [GameModesEnum::Classic, GameModesEnum::TimeAttack, GameModesEnum::Survival, GameModesEnum::Zen, GameModesEnum::Fog]
+        .into_iter()
+        .find(|mode| get_mode_char(*mode) == Some(c))
+}
+
+fn get_style_char(style: LevelStylesEnum) -> char {
+    match style {
+        LevelStylesEnum::Rooms => 'R',
+        LevelStylesEnum::Maze => 'M',
+        LevelStylesEnum::Pillars => 'P',
+        LevelStylesEnum::Symmetric => 'S',
+    }
+}
+
+fn get_style_from_char(c: char) -> Option<LevelStylesEnum> {
+    LEVEL_STYLES.iter().copied().find(|style| get_style_char(*style) == c)
+}
+
+fn to_base36(mut value: u64) -> String {
+    let mut digits = Vec::new();
+
+    loop {
+        digits.push(BASE36[(value % 36) as usize]);
+        value /= 36;
+
+        if value == 0 { break; }
+    }
+
+    digits.iter().rev().map(|digit| *digit as char).collect()
+}
+
+fn from_base36(text: &str) -> Option<u64> {
+    if text.is_empty() { return None; }
+
+    text.chars().try_fold(0u64, |value, c| {
+        let digit = c.to_digit(36)? as u64;
+
+        value.checked_mul(36)?.checked_add(digit)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::load_levels;
+    use crate::models::ModifiersEnum;
+
+    fn get_code(mode: GameModesEnum, difficulty: u16, level: &Level, seed: u64, modifiers: Vec<ModifiersEnum>) -> String {
+        let mut game_state = modes::new_game(difficulty, mode, level, Some(seed));
+
+        game_state.modifiers = modifiers;
+
+        encode(&game_state).unwrap()
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        let built_in = load_levels().0.remove(2);
+        let generated = generate_level(123_456_789, LevelStylesEnum::Maze);
+        let cases = [
+            (GameModesEnum::TimeAttack, 2, &built_in, 0, vec![]),
+            (GameModesEnum::Classic, 4, &generated, u64::MAX, vec![ModifiersEnum::InvisibleBody, ModifiersEnum::LimitedVision]),
+            (GameModesEnum::Fog, 1, &built_in, 36, vec![ModifiersEnum::MovingFood]),
+        ];
+
+        for (mode, difficulty, level, seed, modifiers) in cases {
+            let code = get_code(mode, difficulty, level, seed, modifiers.clone());
+            let decoded = decode(&code).unwrap();
+
+            assert_eq!(decoded, ChallengeCode { mode, difficulty, level: level.source.unwrap(), seed, modifiers }, "{}", code);
+            assert_eq!(decode(&code.to_lowercase()), Ok(decoded));
+        }
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        let codes = [
+            "",
+            "C2",
+            "C2-L1",
+            "C2--1Z141Z3",
+            "C2-L1-",
+            "-L1-1Z141Z3",
+            "C2-L1-1Z141Z3-",
+            "C2-L1-1Z141Z3-IV-X",
+            "C2-L0-1Z141Z3",
+            "C2-L-1Z141Z3",
+            "C2-GX1Z-1Z141Z3",
+            "C2-G-1Z141Z3",
+            "C2-L1-ZZZZZZZZZZZZZZ",
+            "C2-L1-1Z141Z3!",
+            "C2-L1-1Z141Z3-Q",
+            "X2-L1-1Z141Z3",
+            "C9-L1-1Z141Z3",
+            "C-L1-1Z141Z3",
+            "P2-L1-1Z141Z3",
+        ];
+
+        for code in codes {
+            assert!(decode(code).is_err(), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::generator::generate_random_level;
+use crate::modes;
+use crate::models::{Daily, GameModesEnum, ScreensEnum, State, Stats, DAILY_DIFFICULTY};
+use crate::utils::{format_date, get_today, next_random};
+
+// Everyone playing on the same (UTC) day gets the same level and the same food,
+// power-ups and bonuses, at the same difficulty. Only the first attempt of the
+// day is ranked, and while it is going picking the challenge again resumes it.
+pub fn start_daily(state: &mut State) {
+    let day = get_today();
+
+    if is_daily_in_progress(state, day) {
+        state.app_state.selected_screen = ScreensEnum::Game;
+        state.app_state.screen_changed = true;
+        state.game_state.freeze = true;
+        return;
+    }
+
+    let seed = get_daily_seed(day);
+    let level = generate_random_level(seed);
+    let app_state = &mut state.app_state;
+    let ranked = app_state.stats.last_daily != Some(day);
+
+    if ranked {
+        app_state.stats.daily_streak = get_streak(&app_state.stats, day) + 1;
+        app_state.stats.last_daily = Some(day);
+    }
+
+    let mut game_state = modes::new_game(DAILY_DIFFICULTY, GameModesEnum::Daily, &level, Some(seed));
+
+    game_state.daily = Some(Daily { day, ranked });
+
+    state.game_state = game_state;
+    app_state.game_started = true;
+    app_state.selected_screen = ScreensEnum::Game;
+    app_state.screen_changed = true;
+    app_state.dirty = true;
+}
+
+pub fn is_daily_in_progress(state: &State, day: u64) -> bool {
+    state.app_state.game_started && !state.game_state.game_over && state.game_state.daily.is_some_and(|daily| daily.day == day)
+}
+
+pub fn get_daily_seed(day: u64) -> u64 {
+    let mut seed = day;
+
+    next_random(&mut seed)
+}
+
+// The streak only lasts while yesterday's (or today's) challenge was played.
+pub fn get_streak(stats: &Stats, today: u64) -> u64 {
+    match stats.last_daily {
+        Some(day) if day + 1 >= today => stats.daily_streak,
+        _ => 0,
+    }
+}
+
+pub fn get_summary(daily: &Daily) -> String {
+    if daily.ranked {
+        format!("Daily {}", format_date(daily.day))
+    } else {
+        format!("Daily {} (practice)", format_date(daily.day))
+    }
+}
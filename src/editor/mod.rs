@@ -0,0 +1,437 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crossterm::event::{KeyCode, KeyEvent};
+use crate::camera;
+use crate::game::{get_board_offset, get_preview_state, render_board};
+use crate::hazards::create_hazards;
+use crate::tiles::get_tile_cells;
+use crate::levels::{format_coord, get_edges_name, get_portal_digit, get_portal_index, get_selected_level, level_to_file, load_levels, validate_level, MAX_PORTAL_PAIRS};
+use crate::models::{Coord, DirectionsEnum, EdgesEnum, EditorState, GameState, HazardDefinition, Level, ScreensEnum, State, HAZARD, LEVELS_DIR, MAX_WORLD_SIZE, PORTAL, SCREEN_SIZE};
+use crate::utils::{outln, set_col_position, wrap_text};
+
+const MIN_BOARD_SIZE: usize = 2;
+const MAX_NAME_LENGTH: usize = 24;
+const MESSAGE_LINES: usize = 3;
+
+pub fn open_editor(state: &mut State) {
+    let app_state = &mut state.app_state;
+    let level = Level { source: None, ..get_selected_level(app_state) };
+
+    app_state.editor.cursor = level.start;
+    app_state.editor.level = level;
+    app_state.editor.naming = false;
+    app_state.editor.pending_portal = None;
+    app_state.editor.saved_as = None;
+    app_state.editor.messages = vec![String::from("Editing a copy, save writes a new file")];
+    app_state.selected_screen = ScreensEnum::Editor;
+    app_state.screen_changed = true;
+}
+
+pub fn process_keyboard_event(state: &mut State, key_event: KeyEvent) {
+    state.app_state.screen_changed = true;
+
+    if state.app_state.editor.naming {
+        process_naming_event(state, key_event);
+        return;
+    }
+
+    let editor = &mut state.app_state.editor;
+
+    match key_event.code {
+        KeyCode::Up => move_cursor(editor.level.height, &mut editor.cursor.0, false),
+        KeyCode::Down => move_cursor(editor.level.height, &mut editor.cursor.0, true),
+        KeyCode::Left => move_cursor(editor.level.width, &mut editor.cursor.1, false),
+        KeyCode::Right => move_cursor(editor.level.width, &mut editor.cursor.1, true),
+        KeyCode::Char(' ') => toggle_wall(state),
+        KeyCode::Char('s') => set_start(state),
+        KeyCode::Char('d') => rotate_start_direction(state),
+        KeyCode::Char('e') => rotate_edges(state),
+        KeyCode::Char('o') => toggle_portal(state),
+        KeyCode::Char('k') => toggle_item(state, |level| &mut level.keys, "key"),
+        KeyCode::Char('l') => toggle_item(state, |level| &mut level.doors, "door"),
+        KeyCode::Char('[') => resize_board(state, 0, -1),
+        KeyCode::Char(']') => resize_board(state, 0, 1),
+        KeyCode::Char('-') => resize_board(state, -1, 0),
+        KeyCode::Char('+') | KeyCode::Char('=') => resize_board(state, 1, 0),
+        KeyCode::Char('n') => start_naming(state),
+        KeyCode::Char('v') => run_validator(state),
+        KeyCode::Char('p') => start_play_test(state),
+        KeyCode::Char('w') => save_level(state),
+        KeyCode::Esc => back_to_menu(state),
+        _ => {}
+    }
+}
+
+pub fn render(state: &mut State) {
+    let app_state = &state.app_state;
+    let editor = &app_state.editor;
+    let level = &editor.level;
+    let offset = get_board_offset(app_state.view_offset.0, level);
+    let cursor = if editor.naming { None } else { Some(editor.cursor) };
+    let help = [
+        "Space wall  O portal  S start",
+        "K key  L door",
+        "D facing  E edge  N name",
+        "[ ] width  - + height",
+        "V check  P play  W save",
+    ];
+
+    let mut preview = get_preview_state(app_state.difficulty, level);
+
+    preview.camera = camera::center_on(level, editor.cursor);
+
+    if let Some((i, j)) = editor.pending_portal {
+        preview.grid[i][j] = PORTAL;
+    }
+
+    for hazard in create_hazards(level) {
+        preview.grid[hazard.position.0][hazard.position.1] = HAZARD;
+    }
+
+    render_board(&preview, offset, cursor, None);
+
+    let title = if editor.naming {
+        format!("Name: {}_", level.name)
+    } else {
+        format!("{} {}x{} {}", level.name, level.width, level.height, get_edges_name(level.edges))
+    };
+
+    outln!();
+    set_col_position(app_state.view_offset.0);
+    outln!("{}", title);
+
+    let messages: Vec<String> = editor.messages
+        .iter()
+        .flat_map(|message| wrap_text(message, SCREEN_SIZE.0 as usize))
+        .take(MESSAGE_LINES)
+        .collect();
+
+    for i in 0..MESSAGE_LINES {
+        set_col_position(app_state.view_offset.0);
+        outln!("{}", messages.get(i).map(|m| m.as_str()).unwrap_or(""));
+    }
+
+    for line in help {
+        set_col_position(app_state.view_offset.0);
+        outln!("{}", line);
+    }
+}
+
+pub fn stop_play_test(state: &mut State) {
+    let editor = &mut state.app_state.editor;
+
+    if let Some((game_state, game_started)) = editor.suspended_game.take() {
+        state.game_state = game_state;
+        state.app_state.game_started = game_started;
+    }
+
+    state.app_state.editor.play_testing = false;
+    state.app_state.selected_screen = ScreensEnum::Editor;
+    state.app_state.screen_changed = true;
+}
+
+fn process_naming_event(state: &mut State, key_event: KeyEvent) {
+    let editor = &mut state.app_state.editor;
+
+    match key_event.code {
+        KeyCode::Char(c) if editor.level.name.chars().count() < MAX_NAME_LENGTH => editor.level.name.push(c),
+        KeyCode::Backspace => { editor.level.name.pop(); }
+        KeyCode::Enter | KeyCode::Esc => {
+            editor.naming = false;
+
+            if editor.level.name.trim().is_empty() {
+                editor.level.name = String::from("Untitled");
+            }
+        }
+        _ => {}
+    }
+}
+
+fn move_cursor(size: usize, position: &mut usize, is_increment: bool) {
+    if is_increment {
+        *position = if *position + 1 >= size { 0 } else { *position + 1 };
+    } else {
+        *position = if *position == 0 { size - 1 } else { *position - 1 };
+    }
+}
+
+fn toggle_wall(state: &mut State) {
+    let editor = &mut state.app_state.editor;
+    let cursor = editor.cursor;
+
+    if cursor == editor.level.start {
+        editor.messages = vec![String::from("The start cell cannot be a wall")];
+        return;
+    }
+
+    let is_wall = editor.level.walls.contains(&cursor);
+
+    clear_cell(editor, cursor);
+
+    if !is_wall {
+        editor.level.walls.push(cursor);
+    }
+
+    editor.messages.clear();
+}
+
+fn toggle_portal(state: &mut State) {
+    let editor = &mut state.app_state.editor;
+    let cursor = editor.cursor;
+
+    if cursor == editor.level.start {
+        editor.messages = vec![String::from("The start cell cannot be a portal")];
+        return;
+    }
+
+    if get_portal_index(&editor.level, cursor).is_some() || editor.pending_portal == Some(cursor) {
+        clear_cell(editor, cursor);
+        editor.messages = vec![String::from("Portal removed")];
+        return;
+    }
+
+    if editor.level.portals.len() == MAX_PORTAL_PAIRS {
+        editor.messages = vec![format!("A level can have at most {} portal pairs", MAX_PORTAL_PAIRS)];
+        return;
+    }
+
+    clear_cell(editor, cursor);
+
+    match editor.pending_portal.take() {
+        Some(pending) => {
+            add_portal_pair(&mut editor.level, (pending, cursor));
+            editor.messages = vec![String::from("Portal pair placed")];
+        }
+        None => {
+            editor.pending_portal = Some(cursor);
+            editor.messages = vec![String::from("Place the partner portal with O")];
+        }
+    }
+}
+
+// Keys and doors; switches and their gates are only set in the level file.
+fn toggle_item(state: &mut State, get_items: fn(&mut Level) -> &mut Vec<Coord>, name: &str) {
+    let editor = &mut state.app_state.editor;
+    let cursor = editor.cursor;
+
+    if cursor == editor.level.start {
+        editor.messages = vec![format!("The start cell cannot be a {}", name)];
+        return;
+    }
+
+    let is_placed = get_items(&mut editor.level).contains(&cursor);
+
+    clear_cell(editor, cursor);
+
+    if !is_placed {
+        get_items(&mut editor.level).push(cursor);
+    }
+
+    editor.messages.clear();
+}
+
+// A new pair takes the lowest digit no other pair is using.
+fn add_portal_pair(level: &mut Level, pair: (Coord, Coord)) {
+    let mut digits: Vec<usize> = (0..level.portals.len()).map(|index| get_portal_digit(level, index)).collect();
+    let digit = (0..MAX_PORTAL_PAIRS).find(|digit| !digits.contains(digit)).unwrap_or(digits.len());
+
+    digits.push(digit);
+    level.portals.push(pair);
+    level.portal_digits = digits;
+}
+
+fn retain_portals(level: &mut Level, keep: impl Fn(&(Coord, Coord)) -> bool) {
+    let (portals, portal_digits) = (0..level.portals.len())
+        .filter(|index| keep(&level.portals[*index]))
+        .map(|index| (level.portals[index], get_portal_digit(level, index)))
+        .unzip();
+
+    level.portals = portals;
+    level.portal_digits = portal_digits;
+}
+
+// Removes whatever is placed on the cell; a removed portal takes its partner with it.
+fn clear_cell(editor: &mut EditorState, cursor: Coord) {
+    editor.level.walls.retain(|wall| *wall != cursor);
+    retain_portals(&mut editor.level, |(a, b)| *a != cursor && *b != cursor);
+    editor.level.keys.retain(|key| *key != cursor);
+    editor.level.doors.retain(|door| *door != cursor);
+
+    if editor.pending_portal == Some(cursor) {
+        editor.pending_portal = None;
+    }
+}
+
+fn set_start(state: &mut State) {
+    let editor = &mut state.app_state.editor;
+    let cursor = editor.cursor;
+
+    clear_cell(editor, cursor);
+    editor.level.start = cursor;
+    editor.messages = vec![format!("Start moved to {}", format_coord(cursor))];
+}
+
+fn rotate_start_direction(state: &mut State) {
+    let level = &mut state.app_state.editor.level;
+
+    level.start_direction = match level.start_direction {
+        DirectionsEnum::Up => DirectionsEnum::Right,
+        DirectionsEnum::Right => DirectionsEnum::Down,
+        DirectionsEnum::Down => DirectionsEnum::Left,
+        DirectionsEnum::Left => DirectionsEnum::Up,
+    };
+}
+
+fn rotate_edges(state: &mut State) {
+    let level = &mut state.app_state.editor.level;
+
+    level.edges = match level.edges {
+        EdgesEnum::Wrap => EdgesEnum::Solid,
+        EdgesEnum::Solid => EdgesEnum::WrapHorizontal,
+        EdgesEnum::WrapHorizontal => EdgesEnum::WrapVertical,
+        EdgesEnum::WrapVertical => EdgesEnum::Wrap,
+    };
+}
+
+fn resize_board(state: &mut State, height_change: i32, width_change: i32) {
+    let editor = &mut state.app_state.editor;
+    let level = &mut editor.level;
+    let height = (level.height as i32 + height_change).clamp(MIN_BOARD_SIZE as i32, MAX_WORLD_SIZE as i32) as usize;
+    let width = (level.width as i32 + width_change).clamp(MIN_BOARD_SIZE as i32, MAX_WORLD_SIZE as i32) as usize;
+
+    level.height = height;
+    level.width = width;
+    level.walls.retain(|(i, j)| *i < height && *j < width);
+    retain_portals(level, |(a, b)| a.0 < height && a.1 < width && b.0 < height && b.1 < width);
+    level.keys.retain(|(i, j)| *i < height && *j < width);
+    level.doors.retain(|(i, j)| *i < height && *j < width);
+    level.tiles.retain(|tile| get_tile_cells(tile).iter().all(|(i, j)| *i < height && *j < width));
+    level.switches.retain(|switch| {
+        switch.gates.iter().chain([&switch.position]).all(|(i, j)| *i < height && *j < width)
+    });
+    level.hazards.retain(|hazard| {
+        let cells = match hazard {
+            HazardDefinition::Patrol { path, .. } => path.clone(),
+            HazardDefinition::Bounce { start, .. } => vec![*start],
+        };
+
+        cells.iter().all(|(i, j)| *i < height && *j < width)
+    });
+    level.start = clamp_coord(level.start, height, width);
+    editor.pending_portal = editor.pending_portal.filter(|(i, j)| *i < height && *j < width);
+    editor.cursor = clamp_coord(editor.cursor, height, width);
+
+    let start = level.start;
+
+    clear_cell(editor, start);
+    editor.messages.clear();
+}
+
+fn clamp_coord(coord: Coord, height: usize, width: usize) -> Coord {
+    (coord.0.min(height - 1), coord.1.min(width - 1))
+}
+
+fn start_naming(state: &mut State) {
+    let editor = &mut state.app_state.editor;
+
+    editor.naming = true;
+    editor.messages = vec![String::from("Type a name, Enter to finish")];
+}
+
+fn run_validator(state: &mut State) {
+    let editor = &mut state.app_state.editor;
+
+    editor.messages = match validate_level(&editor.level) {
+        Ok(()) => vec![String::from("Level is playable")],
+        Err(issues) => issues,
+    };
+}
+
+fn start_play_test(state: &mut State) {
+    if let Err(issues) = validate_level(&state.app_state.editor.level) {
+        state.app_state.editor.messages = issues;
+        return;
+    }
+
+    let app_state = &mut state.app_state;
+    let game_state = GameState::new(app_state.difficulty, &app_state.editor.level);
+    let suspended_game = std::mem::replace(&mut state.game_state, game_state);
+
+    app_state.editor.suspended_game = Some((suspended_game, app_state.game_started));
+    app_state.editor.play_testing = true;
+    app_state.selected_screen = ScreensEnum::Game;
+}
+
+fn save_level(state: &mut State) {
+    if let Err(issues) = validate_level(&state.app_state.editor.level) {
+        state.app_state.editor.messages = issues;
+        return;
+    }
+
+    let app_state = &mut state.app_state;
+    let editor = &mut app_state.editor;
+    let own_name = editor.saved_as.as_ref().map(|(_, name)| name.as_str());
+    let name = get_free_name(&app_state.levels, &editor.level.name, own_name);
+    let path = match &editor.saved_as {
+        Some((path, _)) => path.clone(),
+        None => get_free_path(&name),
+    };
+    let level = Level { name: name.clone(), ..editor.level.clone() };
+
+    if let Err(error) = write_level_file(&path, &level) {
+        editor.messages = vec![format!("Cannot save {}: {}", path.display(), error)];
+        return;
+    }
+
+    editor.level.name = name.clone();
+    editor.messages = vec![format!("Saved \"{}\" to {}", name, path.display())];
+    editor.saved_as = Some((path, name));
+
+    let (levels, level_errors) = load_levels();
+
+    app_state.levels = levels;
+    app_state.level_errors = level_errors;
+    app_state.level = app_state.level.min(app_state.levels.len() as u16);
+}
+
+fn write_level_file(path: &Path, level: &Level) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&level_to_file(level)).map_err(|e| e.to_string())?;
+
+    fs::create_dir_all(LEVELS_DIR).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+// A copy never takes the name of another level in the menu, "Box" is saved
+// as "Box 2" and so on. `own_name` is the name this copy was saved under before.
+fn get_free_name(levels: &[Level], name: &str, own_name: Option<&str>) -> String {
+    let is_taken = |candidate: &str| own_name != Some(candidate) && levels.iter().any(|level| level.name == candidate);
+
+    if !is_taken(name) { return name.to_string(); }
+
+    (2..).map(|number| format!("{} {}", name, number)).find(|candidate| !is_taken(candidate)).unwrap()
+}
+
+// Existing files are never overwritten by a new copy.
+fn get_free_path(name: &str) -> PathBuf {
+    let stem = get_file_stem(name);
+    let path = Path::new(LEVELS_DIR).join(format!("{}.json", stem));
+
+    if !path.exists() { return path; }
+
+    (2..).map(|number| Path::new(LEVELS_DIR).join(format!("{}_{}.json", stem, number))).find(|path| !path.exists()).unwrap()
+}
+
+fn get_file_stem(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if stem.is_empty() { String::from("level") } else { stem }
+}
+
+fn back_to_menu(state: &mut State) {
+    state.app_state.selected_screen = ScreensEnum::Menu;
+}
@@ -0,0 +1,93 @@
+use crate::modifiers;
+use crate::models::{Coord, EdgesEnum, GameModesEnum, GameState, Level, VisibilityEnum, FOG_RADIUS, WALL};
+
+// The mask `render_game` draws the board through. Fog games see along lines of
+// sight around the head and keep the walls they have already seen, dimmed; the
+// limited vision modifier cuts every mode down to a small square on top of that.
+pub fn get_visibility_mask(game_state: &GameState) -> Vec<Vec<VisibilityEnum>> {
+    let level = &game_state.level;
+    let fog = game_state.mode == GameModesEnum::Fog;
+    let in_sight = if fog { get_cells_in_sight(game_state) } else { Vec::new() };
+    let mut mask = vec![vec![VisibilityEnum::Visible; level.width]; level.height];
+
+    for (i, row) in mask.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            let visible = (!fog || in_sight.contains(&(i, j))) && modifiers::is_visible(game_state, (i, j));
+
+            *cell = if visible { VisibilityEnum::Visible }
+                else if fog && game_state.remembered_walls.contains(&(i, j)) { VisibilityEnum::Remembered }
+                else { VisibilityEnum::Hidden };
+        }
+    }
+
+    mask
+}
+
+// Called whenever the head moves, so walls stay on the map once seen. Survival
+// walls and closed gates are remembered the same way once they come into
+// sight, and forgotten once they are seen open again.
+pub fn remember_walls(game_state: &mut GameState) {
+    if game_state.mode != GameModesEnum::Fog { return; }
+
+    for (i, j) in get_cells_in_sight(game_state) {
+        let is_remembered = game_state.remembered_walls.contains(&(i, j));
+
+        if game_state.grid[i][j] == WALL && !is_remembered {
+            game_state.remembered_walls.push((i, j));
+        } else if game_state.grid[i][j] != WALL && is_remembered {
+            game_state.remembered_walls.retain(|wall| *wall != (i, j));
+        }
+    }
+}
+
+// Every cell within `FOG_RADIUS` of the head whose line of sight is not blocked
+// by a wall. The wall itself is seen, what lies behind it is not. Lines of sight
+// go across the edges the level wraps around.
+fn get_cells_in_sight(game_state: &GameState) -> Vec<Coord> {
+    let level = &game_state.level;
+    let head = game_state.snake_body[0];
+    let radius = FOG_RADIUS as i64;
+    let mut cells = Vec::new();
+
+    for di in -radius..=radius {
+        for dj in -radius..=radius {
+            if di * di + dj * dj > radius * radius { continue; }
+
+            let Some(cell) = get_offset_cell(level, head, (di, dj)) else { continue; };
+
+            if !cells.contains(&cell) && is_in_sight(game_state, head, (di, dj)) {
+                cells.push(cell);
+            }
+        }
+    }
+
+    cells
+}
+
+// Walks the line from the head to the offset and fails on the first wall that
+// is not the target itself.
+fn is_in_sight(game_state: &GameState, head: Coord, offset: (i64, i64)) -> bool {
+    let steps = offset.0.abs().max(offset.1.abs());
+
+    (1..steps).all(|step| {
+        let di = (offset.0 * step * 2 + steps).div_euclid(steps * 2);
+        let dj = (offset.1 * step * 2 + steps).div_euclid(steps * 2);
+
+        get_offset_cell(&game_state.level, head, (di, dj)).is_some_and(|(i, j)| game_state.grid[i][j] != WALL)
+    })
+}
+
+fn get_offset_cell(level: &Level, (row, col): Coord, (di, dj): (i64, i64)) -> Option<Coord> {
+    let wraps_rows = matches!(level.edges, EdgesEnum::Wrap | EdgesEnum::WrapVertical);
+    let wraps_cols = matches!(level.edges, EdgesEnum::Wrap | EdgesEnum::WrapHorizontal);
+    let i = wrap_index(row as i64 + di, level.height, wraps_rows)?;
+    let j = wrap_index(col as i64 + dj, level.width, wraps_cols)?;
+
+    Some((i, j))
+}
+
+fn wrap_index(index: i64, size: usize, wraps: bool) -> Option<usize> {
+    if wraps { return Some(index.rem_euclid(size as i64) as usize); }
+
+    usize::try_from(index).ok().filter(|index| *index < size)
+}
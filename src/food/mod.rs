@@ -0,0 +1,69 @@
+use crate::models::{Coord, Food, FoodKindsEnum, FoodRule, FoodSpawn, GameState, DEFAULT_FOOD_TABLE, FOOD_RULES, MAX_EXTRA_FOOD, MODE_FOOD_TABLES};
+use crate::utils::get_game_random_int;
+
+pub fn get_rule(kind: FoodKindsEnum) -> &'static FoodRule {
+    FOOD_RULES.iter().find(|rule| rule.kind == kind).unwrap()
+}
+
+// The level's own table comes first, then the one for the game mode.
+pub fn get_food_table(game_state: &GameState) -> &[FoodSpawn] {
+    if !game_state.level.food_table.is_empty() { return &game_state.level.food_table; }
+
+    MODE_FOOD_TABLES
+        .iter()
+        .find(|table| table.mode == game_state.mode)
+        .map(|table| table.table)
+        .unwrap_or(&DEFAULT_FOOD_TABLE)
+}
+
+pub fn get_food_at(game_state: &GameState, coord: Coord) -> Option<&Food> {
+    game_state.food.iter().find(|food| food.position == coord)
+}
+
+pub fn has_normal_food(game_state: &GameState) -> bool {
+    game_state.food.iter().any(|food| food.kind == FoodKindsEnum::Normal)
+}
+
+pub fn add_food(game_state: &mut GameState, kind: FoodKindsEnum, free_cell: Option<Coord>) {
+    let Some(position) = free_cell else { return; };
+
+    game_state.food.push(Food { kind, position, expires_in: get_rule(kind).lifetime });
+}
+
+pub fn tick_food(game_state: &mut GameState) {
+    for food in game_state.food.iter_mut() {
+        food.expires_in = food.expires_in.map(|steps| steps.saturating_sub(1));
+    }
+
+    game_state.food.retain(|food| food.expires_in != Some(0));
+}
+
+pub fn take_food_at(game_state: &mut GameState, coord: Coord) -> Option<Food> {
+    let index = game_state.food.iter().position(|food| food.position == coord)?;
+
+    Some(game_state.food.remove(index))
+}
+
+// Picks the kind of the extra food that joins the normal one, if any.
+pub fn roll_extra_food(game_state: &mut GameState) -> Option<FoodKindsEnum> {
+    let extras = game_state.food.iter().filter(|food| food.kind != FoodKindsEnum::Normal).count();
+
+    if extras >= MAX_EXTRA_FOOD { return None; }
+
+    let table = get_food_table(game_state).to_vec();
+    let total_weight: usize = table.iter().map(|spawn| spawn.weight).sum();
+
+    if total_weight == 0 { return None; }
+
+    let mut roll = get_game_random_int(&mut game_state.rng, 0, total_weight);
+
+    for spawn in &table {
+        if roll < spawn.weight {
+            return Some(spawn.kind).filter(|kind| *kind != FoodKindsEnum::Normal);
+        }
+
+        roll -= spawn.weight;
+    }
+
+    None
+}
@@ -1,87 +1,402 @@
+use std::time::Duration;
 use crossterm::event::{KeyCode, KeyEvent};
-use crate::FIELD_SIZE;
-use crate::models::{AppState, Coord, DirectionsEnum, GameState, ScreensEnum, State, BONUS, FOOD, LEVELS, SCREEN_SIZE, SNAKE, WALL};
-use crate::utils::{get_random_free_cell, get_random_int, print_empty_lines, set_col_position};
+use crate::bonus;
+use crate::camera;
+use crate::campaign;
+use crate::codes;
+use crate::daily;
+use crate::editor;
+use crate::fog;
+use crate::food;
+use crate::hazards;
+use crate::modifiers;
+use crate::modes;
+use crate::power_ups;
+use crate::recorder;
+use crate::puzzle;
+use crate::survival;
+use crate::tiles;
+use crate::triggers;
+use crate::zen;
+use crate::levels::{get_edges_name, get_portal_char, get_portal_digit, get_portal_index, get_step};
+use crate::models::{AppState, Coord, DirectionsEnum, EdgesEnum, FoodKindsEnum, GameModesEnum, GameState, LeaderboardEntry, Level, ModifiersEnum, PowerUpsEnum, ScreensEnum, SpeedCurve, State, VisibilityEnum, BONUS, CAMPAIGN_STAGES, FOOD, HAZARD, PORTAL, POWER_UP, SCREEN_SIZE, SNAKE, SPEED_CURVES, VICTORY_BONUS, WALL};
+use crate::utils::{get_random_free_cell, get_short_seed, out, outln, print_empty_lines, set_col_position};
 
 impl GameState {
-    pub fn new(difficulty: u16, level: u16) -> Self {
-        get_new_game_state(difficulty, level)
+    pub fn new(difficulty: u16, level: &Level) -> Self {
+        get_new_game_state(difficulty, level, None)
+    }
+
+    pub fn new_seeded(difficulty: u16, level: &Level, seed: Option<u64>) -> Self {
+        get_new_game_state(difficulty, level, seed)
     }
 }
 
 pub fn process_keyboard_event(state: &mut State, key_event: KeyEvent) {
+    if key_event.code == KeyCode::Esc && state.app_state.editor.play_testing {
+        editor::stop_play_test(state);
+        return;
+    }
+
     let game_state = &mut state.game_state;
     let app_state = &mut state.app_state;
+    let is_speed_key = matches!(key_event.code, KeyCode::Char('+' | '=' | '-')) && zen::is_zen(game_state);
 
-    game_state.freeze = false;
+    // Changing the speed does not count as picking a new direction after a bump.
+    if !is_speed_key { game_state.freeze = false; }
 
     match key_event.code {
-        KeyCode::Down => game_state.next_direction = DirectionsEnum::Down,
-        KeyCode::Up => game_state.next_direction = DirectionsEnum::Up,
-        KeyCode::Left => game_state.next_direction = DirectionsEnum::Left,
-        KeyCode::Right => game_state.next_direction = DirectionsEnum::Right,
+        KeyCode::Down => turn_snake(state, DirectionsEnum::Down),
+        KeyCode::Up => turn_snake(state, DirectionsEnum::Up),
+        KeyCode::Left => turn_snake(state, DirectionsEnum::Left),
+        KeyCode::Right => turn_snake(state, DirectionsEnum::Right),
         KeyCode::Esc => back_to_menu(app_state),
-        KeyCode::Enter => {
-            if game_state.game_over {
-                *game_state = GameState::new(app_state.difficulty, app_state.level);
-            }
-        }
+        KeyCode::Char('+') | KeyCode::Char('=') if zen::is_zen(game_state) => zen::change_speed(game_state, true),
+        KeyCode::Char('-') if zen::is_zen(game_state) => zen::change_speed(game_state, false),
+        KeyCode::Enter if game_state.game_over => restart_game(state),
         _ => {}
     }
 }
 
+// In a puzzle the snake only moves when an arrow key is pressed, and a key that
+// would turn it back on itself does nothing.
+fn turn_snake(state: &mut State, direction: DirectionsEnum) {
+    let game_state = &mut state.game_state;
+
+    let direction = modifiers::get_direction(game_state, direction);
+
+    game_state.next_direction = direction;
+
+    if game_state.puzzle.is_none() || game_state.game_over { return; }
+    if hazards::get_opposite_direction(game_state.snake_direction) == direction { return; }
+
+    step(state);
+}
+
+fn restart_game(state: &mut State) {
+    if state.game_state.campaign.is_some() {
+        campaign::start_campaign(state);
+        return;
+    }
+
+    if state.game_state.daily.is_some() {
+        daily::start_daily(state);
+        return;
+    }
+
+    let game_state = &state.game_state;
+    let seed = if game_state.from_code { game_state.seed } else { Some(get_short_seed()) };
+    let from_code = game_state.from_code;
+    let modifiers = game_state.modifiers.clone();
+
+    state.game_state = modes::new_game(game_state.difficulty, game_state.mode, &game_state.level, seed);
+    state.game_state.from_code = from_code;
+    state.game_state.modifiers = modifiers;
+}
+
+// Called on every loop iteration with the wall-clock time since the previous
+// one, so the game clock runs at real speed whatever the step interval is.
+pub fn update_game_clock(state: &mut State, elapsed: Duration) {
+    if state.game_state.game_over || state.game_state.freeze { return; }
+
+    modes::tick_clock(&mut state.game_state, elapsed);
+
+    if modes::is_time_up(&state.game_state) {
+        end_game(state);
+    }
+}
+
 pub fn update_game_state(state: &mut State) {
-    let ate_food = state.game_state.ate_food;
+    if state.game_state.puzzle.is_some() { return; }
+
+    step(state);
+}
 
+fn step(state: &mut State) {
     if state.game_state.game_over || state.game_state.freeze { return; }
 
-    state.game_state.ate_food = false;
+    let bonus_rule = bonus::get_rule(state.game_state.difficulty);
+
+    bonus::update_bonus_value(&mut state.game_state, bonus_rule);
+
+    state.game_state.steps += 1;
+    let hazard_positions = hazards::get_positions(&state.game_state);
+    hazards::move_hazards(&mut state.game_state);
+    power_ups::tick_power_ups(&mut state.game_state);
+    food::tick_food(&mut state.game_state);
+    tiles::tick_tiles(&mut state.game_state);
+
+    if tiles::hold_snake(&mut state.game_state) {
+        if !hit_hazard(state, false) { set_grid_values(state); }
+        return;
+    }
 
     update_curr_direction(&mut state.game_state);
-    update_bonus_value(&mut state.game_state);
 
     let head = state.game_state.snake_body[0];
-    let next_head = get_next_head(&mut state.game_state, head);
-
-    if state.game_state.snake_body.contains(&next_head) ||
-        state.game_state.grid[next_head.0][next_head.1] == WALL
-    {
-        state.game_state.game_over = true;
-        state.app_state.game_started = false;
-        state.app_state.screen_changed = true;
-        state.app_state.new_score = Some(state.game_state.score);
+    let next_head = get_next_head(&state.game_state, head);
+
+    if let Some(next_head) = next_head {
+        triggers::unlock_door(&mut state.game_state, next_head);
+    }
+
+    let next_head = next_head.filter(|next_head| state.game_state.grid[next_head.0][next_head.1] != WALL);
+
+    let Some(next_head) = next_head else {
+        if zen::is_zen(&state.game_state) {
+            zen::bump(&mut state.game_state);
+        } else {
+            end_game(state);
+        }
+
         return;
+    };
+
+    let is_ghost = power_ups::has_effect(&state.game_state, PowerUpsEnum::Ghost);
+    let hit_index = state.game_state.snake_body.iter().position(|cell| *cell == next_head).filter(|_| !is_ghost);
+
+    if let Some(index) = hit_index {
+        if !zen::is_zen(&state.game_state) {
+            end_game(state);
+            return;
+        }
+
+        zen::take_hit(&mut state.game_state, index);
     }
 
-    if !ate_food {
-        state.game_state.snake_body.pop();
+    // After a cut the tail is already gone, so the snake does not lose another cell.
+    if hit_index.is_none() {
+        if state.game_state.pending_growth > 0 {
+            state.game_state.pending_growth -= 1;
+        } else {
+            state.game_state.snake_body.pop();
+        }
     }
 
     state.game_state.snake_body.insert(0, next_head);
+    camera::follow_head(&mut state.game_state);
+    triggers::enter_cell(&mut state.game_state, next_head);
+    tiles::enter_tile(&mut state.game_state, next_head);
+    triggers::update_gates(&mut state.game_state);
+
+    let is_crossed = hazards::is_crossed(&state.game_state, &hazard_positions, head, next_head);
+
+    if hit_hazard(state, is_crossed) { return; }
+
     set_grid_values(state);
 
-    if is_bonus(&state.game_state, next_head) {
-        state.game_state.score += state.game_state.bonus_value;
-        state.game_state.bonus_position = None;
-        state.game_state.bonus_value = 100;
+    if state.game_state.puzzle.is_some() {
+        match puzzle::update_puzzle(&mut state.game_state, next_head) {
+            Some(true) => solve_puzzle(state),
+            Some(false) => end_game(state),
+            None => set_grid_values(state),
+        }
+
+        return;
     }
 
-    if state.game_state.food_position == next_head {
-        state.game_state.ate_food = true;
-        state.game_state.food_position = get_random_free_cell(&state.game_state.grid);
-        state.game_state.score += get_food_value(&state.app_state);
-        state.game_state.food_eaten += 1;
-        state.game_state.food_for_bonus_needed = state.game_state.food_for_bonus_needed.saturating_sub(1);
+    if bonus::is_bonus(&state.game_state, next_head) {
+        let points = state.game_state.bonus_value * power_ups::get_score_multiplier(&state.game_state);
+
+        state.game_state.score += modifiers::apply_score(&state.game_state, points);
+        state.game_state.bonus_cells.clear();
     }
 
-    if state.game_state.food_for_bonus_needed == 0 {
-        state.game_state.ate_food = true;
-        state.game_state.bonus_position = get_bonus_position(&mut state.game_state);
-        state.game_state.bonus_value = 100;
-        state.game_state.food_for_bonus_needed = 5;
+    eat_food(state, next_head);
+
+    if campaign::is_stage_cleared(&state.game_state) {
+        if !campaign::advance_stage(state) { win_campaign(state); }
+
+        return;
     }
 
+    if is_board_full(&state.game_state) {
+        win_game(state);
+        return;
+    }
+
+    // With no room for any shape the bonus stays due and is retried on the next step.
+    if !modifiers::has_modifier(&state.game_state, ModifiersEnum::NoBonus) &&
+        state.game_state.food_for_bonus_needed == 0 &&
+        bonus::spawn_bonus(&mut state.game_state, bonus_rule) {
+        state.game_state.pending_growth += 1;
+        state.game_state.food_for_bonus_needed = bonus_rule.trigger;
+    }
+
+    power_ups::collect_power_up(&mut state.game_state, next_head);
     set_grid_values(state);
+
+    if modifiers::is_food_moving(&state.game_state) {
+        state.game_state.food.retain(|food| food.kind != FoodKindsEnum::Normal);
+    }
+
+    if !food::has_normal_food(&state.game_state) {
+        let free_cell = get_spawn_position(&mut state.game_state);
+
+        food::add_food(&mut state.game_state, FoodKindsEnum::Normal, free_cell);
+        set_grid_values(state);
+    }
+
+    let free_cell = get_spawn_position(&mut state.game_state);
+
+    power_ups::spawn_power_up(&mut state.game_state, free_cell);
+    power_ups::pull_food(&mut state.game_state);
+    set_grid_values(state);
+    survival::spawn_wall(&mut state.game_state);
+    fog::remember_walls(&mut state.game_state);
+}
+
+fn eat_food(state: &mut State, head: Coord) {
+    let Some(eaten) = food::take_food_at(&mut state.game_state, head) else { return; };
+    let rule = food::get_rule(eaten.kind);
+    let game_state = &mut state.game_state;
+
+    let points = get_food_value(game_state.difficulty) * rule.value * power_ups::get_score_multiplier(game_state);
+
+    game_state.score += modifiers::apply_score(game_state, points);
+
+    if rule.growth < 0 {
+        shrink_snake(game_state, rule.growth.unsigned_abs() as usize);
+    } else {
+        game_state.pending_growth += rule.growth as u64;
+    }
+
+    if rule.value > 0 {
+        modes::add_food_time(game_state);
+        game_state.food_eaten += 1;
+        game_state.food_for_bonus_needed = game_state.food_for_bonus_needed.saturating_sub(1);
+    }
+
+    if state.app_state.speed_up && rule.value > 0 {
+        speed_up(game_state, get_speed_curve(game_state.difficulty));
+    }
+
+    if eaten.kind != FoodKindsEnum::Normal { return; }
+
+    let free_cell = get_spawn_position(game_state);
+
+    food::add_food(game_state, FoodKindsEnum::Normal, free_cell);
+
+    if let Some(kind) = food::roll_extra_food(game_state) {
+        let free_cell = get_spawn_position(game_state);
+
+        food::add_food(game_state, kind, free_cell);
+    }
+}
+
+fn speed_up(game_state: &mut GameState, curve: &SpeedCurve) {
+    if !game_state.food_eaten.is_multiple_of(curve.every) { return; }
+
+    game_state.required_ticks = game_state.required_ticks.saturating_sub(1).max(curve.floor);
+}
+
+pub fn get_speed_curve(difficulty: u16) -> &'static SpeedCurve {
+    SPEED_CURVES
+        .iter()
+        .find(|curve| curve.difficulty == difficulty)
+        .unwrap_or(&SPEED_CURVES[SPEED_CURVES.len() - 1])
+}
+
+pub fn shrink_snake(game_state: &mut GameState, amount: usize) {
+    let length = game_state.snake_body.len().saturating_sub(amount).max(1);
+
+    game_state.snake_body.truncate(length);
+    game_state.pending_growth = 0;
+}
+
+pub fn get_required_ticks(game_state: &GameState) -> u64 {
+    (game_state.required_ticks * power_ups::get_tick_multiplier(game_state) / modifiers::get_tick_divisor(game_state) / tiles::get_tick_divisor(game_state)).max(1)
+}
+
+fn end_game(state: &mut State) {
+    if !state.game_state.victory && campaign::lose_life(state) { return; }
+
+    state.game_state.game_over = true;
+    state.app_state.screen_changed = true;
+
+    if let Some(recorder) = state.app_state.recorder.as_mut() {
+        let result = recorder::record_marker(recorder, &get_result_marker(&state.game_state));
+
+        recorder::check_write(&mut state.app_state, result);
+    }
+
+    if state.app_state.editor.play_testing { return; }
+
+    state.app_state.game_started = false;
+    state.app_state.dirty = true;
+
+    if modes::is_ranked(&state.game_state) {
+        let victory = state.game_state.victory;
+
+        state.app_state.new_score = Some(LeaderboardEntry {
+            score: state.game_state.score,
+            victory,
+            mode: state.game_state.mode,
+            day: state.game_state.daily.map(|daily| daily.day),
+            modifiers: state.game_state.modifiers.clone(),
+        });
+        state.app_state.stats.games_played += 1;
+        state.app_state.stats.victories += victory as u64;
+    }
+
+    if state.game_state.campaign.is_some() {
+        state.app_state.campaign = None;
+    }
+}
+
+fn get_result_marker(game_state: &GameState) -> String {
+    let result = if game_state.victory { "won" } else { "lost" };
+
+    if game_state.modifiers.is_empty() {
+        format!("Game {} with {} points", result, game_state.score)
+    } else {
+        format!("Game {} with {} points, modifiers {}", result, game_state.score, modifiers::format_tags(&game_state.modifiers))
+    }
+}
+
+fn solve_puzzle(state: &mut State) {
+    let moves = state.game_state.puzzle.map(|puzzle| puzzle.moves).unwrap_or(0);
+
+    state.game_state.victory = true;
+    set_grid_values(state);
+
+    if !state.app_state.editor.play_testing {
+        puzzle::save_record(&mut state.app_state, &state.game_state.level, moves);
+    }
+
+    end_game(state);
+}
+
+fn win_campaign(state: &mut State) {
+    state.game_state.victory = true;
+    set_grid_values(state);
+    end_game(state);
+}
+
+// In a campaign a full board clears the stage, bonus included, instead of
+// ending the run.
+fn win_game(state: &mut State) {
+    state.game_state.score += VICTORY_BONUS;
+
+    if state.game_state.campaign.is_some() {
+        if !campaign::advance_stage(state) { win_campaign(state); }
+
+        return;
+    }
+
+    state.game_state.victory = true;
+    set_grid_values(state);
+    end_game(state);
+}
+
+// The board is full once the snake covers every cell that is not a wall, a
+// portal or a hazard.
+fn is_board_full(game_state: &GameState) -> bool {
+    (0..game_state.level.height).all(|i| (0..game_state.level.width).all(|j| {
+        let cell = game_state.grid[i][j];
+
+        cell == WALL || cell == PORTAL || hazards::is_hazard(game_state, (i, j)) || game_state.snake_body.contains(&(i, j))
+    }))
 }
 
 pub fn render(state: &mut State) {
@@ -95,77 +410,279 @@ pub fn render(state: &mut State) {
 fn render_game(state: &mut State) {
     let game_state = &mut state.game_state;
     let app_state = &mut state.app_state;
-    let offset = app_state.view_offset.0 + (SCREEN_SIZE.0 - FIELD_SIZE as u16 * 2) / 2;
+    let offset = get_board_offset(app_state.view_offset.0, &game_state.level);
+    let mask = fog::get_visibility_mask(game_state);
+
+    render_board(game_state, offset, None, Some(&mask));
+
+    outln!();
+    set_col_position(offset);
+    out!("Score: {}", game_state.score);
+
+    if let Some(time_left) = modes::get_time_left(game_state) {
+        out!("   Time: {}", modes::format_time(time_left));
+    }
+
+    if game_state.mode == GameModesEnum::Survival {
+        out!("   Next wall: {}", survival::get_steps_to_next_wall(game_state));
+    }
+
+    if game_state.level.edges != EdgesEnum::Wrap {
+        out!("   Edges: {}", get_edges_name(game_state.level.edges));
+    }
+
+    if zen::is_zen(game_state) {
+        out!("   Speed: {} (+/-)", zen::get_speed(game_state));
+    } else if app_state.speed_up {
+        let curve = get_speed_curve(game_state.difficulty);
+
+        out!("   Speed: {}", curve.ticks.saturating_sub(game_state.required_ticks) + 1);
+    }
+
+    if game_state.speed_boost > 0 {
+        out!("   Boost: {}", game_state.speed_boost);
+    }
+
+    outln!();
+
+    if let Some(campaign) = &game_state.campaign {
+        set_col_position(offset);
+        outln!("{}", campaign::get_summary(campaign, game_state));
+    }
+
+    if let Some(puzzle) = &game_state.puzzle {
+        set_col_position(offset);
+        outln!("{}", puzzle::get_summary(puzzle, &game_state.level));
+    }
+
+    if let Some(daily) = &game_state.daily {
+        set_col_position(offset);
+        outln!("{}", daily::get_summary(daily));
+    }
+
+    if let Some(summary) = triggers::get_summary(game_state) {
+        set_col_position(offset);
+        outln!("{}", summary);
+    }
+
+    if !game_state.modifiers.is_empty() {
+        set_col_position(offset);
+        outln!("{}", modifiers::get_summary(&game_state.modifiers));
+    }
+
+    set_col_position(offset);
+    outln!("{}", power_ups::get_effects_summary(game_state));
+}
+
+pub fn get_board_offset(view_offset: u16, level: &Level) -> u16 {
+    let width = camera::get_view_size(level).1 + camera::get_minimap_width(level);
+
+    view_offset + SCREEN_SIZE.0.saturating_sub(width as u16 * 2) / 2
+}
+
+// Draws the part of the board under the camera, with the minimap to the right
+// of it when the world does not fit. Without a mask the whole board is
+// drawn, as in the editor.
+pub fn render_board(game_state: &GameState, offset: u16, cursor: Option<Coord>, mask: Option<&[Vec<VisibilityEnum>]>) {
+    let level = &game_state.level;
     let head = game_state.snake_body[0];
     let dir = game_state.snake_direction;
+    let (view_height, view_width) = camera::get_view_size(level);
+    let (top, left) = game_state.camera;
+    let minimap = if camera::is_scrolling(level) { camera::get_minimap_lines(game_state, mask) } else { Vec::new() };
 
-    for i in 0..FIELD_SIZE {
+    for (i, row) in game_state.grid.iter().enumerate().skip(top).take(view_height) {
         set_col_position(offset);
 
-        for j in 0..FIELD_SIZE {
+        for (j, cell) in row.iter().enumerate().skip(left).take(view_width) {
+            match mask.map(|mask| mask[i][j]) {
+                Some(VisibilityEnum::Hidden) => {
+                    out!("  ");
+                    continue;
+                }
+                Some(VisibilityEnum::Remembered) => {
+                    out!("x ");
+                    continue;
+                }
+                _ => {}
+            }
+
+            let is_cursor = cursor == Some((i, j));
             let is_head = head == (i, j);
-            let is_bonus = game_state.grid[i][j] == BONUS;
-            let is_snake = game_state.grid[i][j] == SNAKE;
-            let is_food = game_state.grid[i][j] == FOOD;
-            let is_wall = game_state.grid[i][j] == WALL;
+            let is_bonus = *cell == BONUS;
+            let is_snake = *cell == SNAKE && !modifiers::is_body_hidden(game_state);
+            let is_food = *cell == FOOD;
+            let is_wall = *cell == WALL;
+            let is_portal = *cell == PORTAL;
+            let is_hazard = *cell == HAZARD;
+            let is_power_up = *cell == POWER_UP;
+
+            if is_portal && !is_cursor {
+                let symbol = get_portal_index(level, (i, j)).map(|index| get_portal_char(get_portal_digit(level, index))).unwrap_or('?');
+
+                out!("{} ", symbol);
+                continue;
+            }
+
+            if let Some(food) = food::get_food_at(game_state, (i, j)).filter(|_| is_food && !is_cursor) {
+                match puzzle::get_food_char(game_state, (i, j)) {
+                    Some(symbol) => out!("{} ", symbol),
+                    None => out!("{}", food::get_rule(food.kind).symbol),
+                }
+
+                continue;
+            }
+
+            if let Some(power_up) = game_state.power_up.as_ref().filter(|_| is_power_up && !is_cursor) {
+                out!("{}", power_ups::get_rule(power_up.kind).symbol);
+                continue;
+            }
 
-            let symbol = if is_bonus { "▒▒" }
+            if let Some(symbol) = triggers::get_symbol(game_state, (i, j)).filter(|_| !is_cursor && !is_head && !is_snake) {
+                out!("{}", symbol);
+                continue;
+            }
+
+            let symbol = if is_cursor { "░░" }
+                else if is_bonus { "▒▒" }
                 else if is_head && dir == DirectionsEnum::Down { "V "}
                 else if is_head && dir == DirectionsEnum::Left { "< " }
                 else if is_head && dir == DirectionsEnum::Right { "> " }
                 else if is_head && dir == DirectionsEnum::Up { "^ " }
                 else if is_snake { "o " }
-                else if is_food { "■ " }
                 else if is_wall { "X "}
-                else {"˙ "};
+                else if is_hazard { "@ " }
+                else { tiles::get_symbol(level, (i, j)).unwrap_or("˙ ") };
 
-            print!("{}", symbol);
+            out!("{}", symbol);
         }
 
-        println!();
-    }
+        if let Some(line) = minimap.get(i - top) {
+            out!("{}", line);
+        }
 
-    println!();
-    set_col_position(offset);
-    println!("Score: {}", game_state.score);
+        outln!();
+    }
 }
 
 fn render_game_result(state: &mut State) {
     print_empty_lines(3);
 
     let offset = state.app_state.view_offset.0;
-    let instructions = [
+    let instructions = if let Some(puzzle) = &state.game_state.puzzle { vec![
+        String::from(if state.game_state.victory { "PUZZLE SOLVED!" } else { "PUZZLE FAILED" }),
+        format!("MOVES: {}/{}", puzzle.moves, state.game_state.level.move_limit.unwrap_or(0)),
+        puzzle::get_best_moves(&state.app_state, &state.game_state.level)
+            .map(|moves| format!("BEST: {}", moves))
+            .unwrap_or_default(),
+        String::from(""),
+        String::from("'Enter' to try again"),
+        String::from("'Esc' to open main manu")
+    ] } else if state.game_state.victory && state.game_state.campaign.is_some() { vec![
+        String::from("CAMPAIGN COMPLETE!"),
+        format!("YOUR SCORE: {}", state.game_state.score),
+        String::from(""),
+        String::from("'Enter' to start a new campaign"),
+        String::from("'Esc' to open main manu")
+    ] } else if let Some(campaign) = &state.game_state.campaign { vec![
         String::from("GAME OVER"),
+        format!("REACHED STAGE {}/{}", campaign.stage + 1, CAMPAIGN_STAGES.len()),
+        format!("YOUR SCORE: {}", state.game_state.score),
+        String::from(""),
+        String::from("'Enter' to start a new campaign"),
+        String::from("'Esc' to open main manu")
+    ] } else if let Some(daily) = &state.game_state.daily { vec![
+        String::from(if state.game_state.victory { "YOU WIN!" } else { "GAME OVER" }),
+        daily::get_summary(daily),
+        format!("YOUR SCORE: {}", state.game_state.score),
+        String::from(""),
+        String::from("'Enter' to practice again"),
+        String::from("'Esc' to open main manu")
+    ] } else if modes::is_time_up(&state.game_state) { vec![
+        String::from("TIME'S UP"),
+        format!("FOOD EATEN: {}", state.game_state.food_eaten),
+        format!("YOUR SCORE: {}", state.game_state.score),
+        String::from(""),
+        String::from("'Enter' to start a new game"),
+        String::from("'Esc' to open main manu")
+    ] } else if state.game_state.mode == GameModesEnum::Survival && !state.game_state.victory { vec![
+        String::from("GAME OVER"),
+        format!("SURVIVED {} STEPS", state.game_state.steps),
+        format!("YOUR SCORE: {}", state.game_state.score),
+        String::from(""),
+        String::from("'Enter' to start a new game"),
+        String::from("'Esc' to open main manu")
+    ] } else if state.game_state.victory { vec![
+        String::from("YOU WIN!"),
+        format!("BOARD CLEARED, +{} POINTS", VICTORY_BONUS),
         format!("YOUR SCORE: {}", state.game_state.score),
         String::from(""),
         String::from("'Enter' to start a new game"),
         String::from("'Esc' to open main manu")
-    ];
+    ] } else { vec![
+        String::from("GAME OVER"),
+        format!("YOUR SCORE: {}", state.game_state.score),
+        String::from(""),
+        String::from("'Enter' to start a new game"),
+        String::from("'Esc' to open main manu")
+    ] };
 
     for instruction in instructions.iter() {
         let actual_offset = offset + (SCREEN_SIZE.0.saturating_sub(instruction.len() as u16)) / 2;
 
         set_col_position(actual_offset);
-        println!("{}", instruction);
+        outln!("{}", instruction);
+    }
+
+    if let Some(code) = codes::encode(&state.game_state) {
+        let line = format!("Code: {}", code);
+
+        outln!();
+        set_col_position(offset + SCREEN_SIZE.0.saturating_sub(line.len() as u16) / 2);
+        outln!("{}", line);
     }
 }
 
 fn set_grid_values(state: &mut State) {
     let game_state = &mut state.game_state;
 
-    for i in 0..FIELD_SIZE {
-        for j in 0..FIELD_SIZE {
-            if game_state.grid[i][j] == WALL { continue; }
+    for i in 0..game_state.level.height {
+        for j in 0..game_state.level.width {
+            if game_state.grid[i][j] == WALL || game_state.grid[i][j] == PORTAL { continue; }
 
-            game_state.grid[i][j] = if (i, j) == game_state.food_position { FOOD }
+            game_state.grid[i][j] = if hazards::is_hazard(game_state, (i, j)) { HAZARD }
+                else if food::get_food_at(game_state, (i, j)).is_some() { FOOD }
                 else if game_state.snake_body.contains(&(i, j)) { SNAKE }
-                else if is_bonus(game_state, (i, j)) { BONUS }
+                else if bonus::is_bonus(game_state, (i, j)) { BONUS }
+                else if game_state.power_up.as_ref().is_some_and(|power_up| power_up.position == (i, j)) { POWER_UP }
                 else { 0 };
         }
     }
 }
 
+// Returns true when a hazard on the snake, or one the head passed through,
+// ended the game; in Zen mode it only cuts the tail off.
+fn hit_hazard(state: &mut State, is_crossed: bool) -> bool {
+    let index = if is_crossed {
+        Some(0)
+    } else {
+        state.game_state.snake_body.iter().position(|cell| hazards::is_hazard(&state.game_state, *cell))
+    };
+
+    let Some(index) = index else { return false; };
+
+    if !zen::is_zen(&state.game_state) {
+        end_game(state);
+        return true;
+    }
+
+    zen::take_hit(&mut state.game_state, index);
+    false
+}
+
 fn update_curr_direction(state: &mut GameState) {
+    if tiles::is_on_ice(state) { return; }
+
     match (state.snake_direction, state.next_direction) {
         (DirectionsEnum::Up, DirectionsEnum::Down) |
         (DirectionsEnum::Down, DirectionsEnum::Up) |
@@ -183,123 +700,104 @@ fn back_to_menu(app_state: &mut AppState) {
     app_state.dirty = true;
 }
 
-fn get_bonus_position(game_state: &mut GameState) -> Option<(usize, usize)> {
-    let mut free_cells: Vec<(usize, usize)> = Vec::new();
+fn get_spawn_position(game_state: &mut GameState) -> Option<Coord> {
+    let mut excluded = hazards::get_hazard_zone(game_state);
 
-    for i in 0..FIELD_SIZE - 1 {
-        for j in 0..FIELD_SIZE - 1 {
-            let free = game_state.grid[i][j] == 0 &&
-                game_state.grid[i][j + 1] == 0 &&
-                game_state.grid[i + 1][j] == 0 &&
-                game_state.grid[i + 1][j + 1] == 0;
+    excluded.extend(&game_state.snake_body);
+    excluded.extend(game_state.food.iter().map(|food| food.position));
+    excluded.extend(game_state.power_up.as_ref().map(|power_up| power_up.position));
+    excluded.extend(triggers::get_item_cells(game_state));
 
-            if free { free_cells.push((i, j)); }
-        }
-    }
-
-    if free_cells.len() != 0 {
-        Some(free_cells[get_random_int(0, free_cells.len())])
-    } else {
-        None
-    }
+    get_random_free_cell(&game_state.grid, &excluded, &mut game_state.rng)
 }
 
-fn update_bonus_value(game_state: &mut GameState) {
-    if game_state.bonus_position.is_none() { return; }
+fn get_new_game_state(difficulty: u16, level: &Level, seed: Option<u64>) -> GameState {
+    let mut game_state = get_preview_state(difficulty, level);
 
-    game_state.bonus_value = game_state.bonus_value.saturating_sub(3);
+    game_state.camera = camera::center_on(level, level.start);
+    game_state.seed = seed;
+    game_state.rng = seed;
 
-    if game_state.bonus_value == 0 {
-        game_state.bonus_position = None;
-    }
-}
-
-fn is_bonus(game_state: &GameState, coord: Coord) -> bool {
-    if game_state.bonus_position.is_none() { return false; }
+    let free_cell = get_spawn_position(&mut game_state);
 
-    let (x, y) = game_state.bonus_position.unwrap();
+    food::add_food(&mut game_state, FoodKindsEnum::Normal, free_cell);
 
-    coord == (x, y) ||
-        coord == (x + 1, y) ||
-        coord == (x, y + 1) ||
-        coord == (x + 1, y + 1)
+    game_state
 }
 
-fn get_new_game_state(difficulty: u16, level: u16) -> GameState {
-    let grid = gen_grid(FIELD_SIZE, level);
-    let food_position = get_random_free_cell(&grid);
-    let required_ticks = match difficulty {
-        1 => 20,
-        2 => 15,
-        3 => 10,
-        4 => 2,
-        _ => 2,
-    };
-
-    let game_state = GameState {
-        snake_direction: DirectionsEnum::Right,
-        next_direction: DirectionsEnum::Right,
-        snake_body: Vec::from([get_starting_position(level)]),
-        ate_food: false,
+// A game state without food, used to draw a level before it is played.
+pub fn get_preview_state(difficulty: u16, level: &Level) -> GameState {
+    let grid = gen_grid(level);
+    let required_ticks = get_speed_curve(difficulty).ticks;
+
+    GameState {
+        difficulty,
+        snake_direction: level.start_direction,
+        next_direction: level.start_direction,
+        snake_body: Vec::from([level.start]),
+        pending_growth: 0,
         game_over: false,
+        victory: false,
         score: 0,
-        bonus_position: None,
+        bonus_cells: Vec::new(),
         food_eaten: 0,
-        bonus_value: 50,
-        food_for_bonus_needed: 5,
+        bonus_value: 0,
+        food_for_bonus_needed: bonus::get_rule(difficulty).trigger,
         freeze: false,
         grid,
-        food_position,
+        food: Vec::new(),
         required_ticks,
-    };
-
-    game_state
+        level: level.clone(),
+        hazards: hazards::create_hazards(level),
+        steps: 0,
+        power_up: None,
+        effects: Vec::new(),
+        campaign: None,
+        mode: GameModesEnum::Classic,
+        play_time: 0,
+        time_limit: None,
+        puzzle: None,
+        daily: None,
+        seed: None,
+        rng: None,
+        from_code: false,
+        modifiers: Vec::new(),
+        remembered_walls: Vec::new(),
+        camera: (0, 0),
+        keys: level.keys.clone(),
+        keys_held: 0,
+        doors: level.doors.clone(),
+        switches_flipped: vec![false; level.switches.len()],
+        speed_boost: 0,
+        stuck: false,
+    }
 }
 
-fn gen_grid(size: usize, level: u16) -> Vec<Vec<u16>> {
-    let level = LEVELS[level as usize];
-    let mut grid = vec![vec![0; size]; size];
+pub fn gen_grid(level: &Level) -> Vec<Vec<u16>> {
+    let mut grid = vec![vec![0; level.width]; level.height];
 
-    for (x, y) in level {
+    for (x, y) in &level.walls {
         grid[*x][*y] = WALL;
     }
 
-    grid
-}
-
-fn get_starting_position(level: u16) -> Coord {
-    match level {
-        0 | 1 => (7, 7),
-        2 => (4, 0),
-        3 | 4 => (0, 0),
-        _ => (0, 0),
+    for (a, b) in &level.portals {
+        grid[a.0][a.1] = PORTAL;
+        grid[b.0][b.1] = PORTAL;
     }
-}
-
-fn get_next_head(game_state: &GameState, head: Coord) -> Coord {
-    let max = FIELD_SIZE - 1;
-    let (mut curr_row, mut curr_col) = head;
 
-    match game_state.snake_direction {
-        DirectionsEnum::Up => {
-            curr_row = if curr_row == 0 { max } else { curr_row - 1 };
-        }
-        DirectionsEnum::Down => {
-            curr_row = if curr_row == max { 0 } else { curr_row + 1 };
-        }
-        DirectionsEnum::Left => {
-            curr_col = if curr_col == 0 { max } else { curr_col - 1 };
-        }
-        DirectionsEnum::Right => {
-            curr_col = if curr_col == max { 0 } else { curr_col + 1 };
-        }
+    for (x, y) in triggers::get_closed_cells(level) {
+        grid[x][y] = WALL;
     }
 
-    (curr_row, curr_col)
+    grid
+}
+
+fn get_next_head(game_state: &GameState, head: Coord) -> Option<Coord> {
+    get_step(&game_state.level, head, tiles::get_move_direction(game_state))
 }
 
-fn get_food_value(state: &AppState) -> u64 {
-    match state.difficulty {
+fn get_food_value(difficulty: u16) -> u64 {
+    match difficulty {
         1 => 1,
         2 => 3,
         3 => 5,
@@ -307,3 +805,102 @@ fn get_food_value(state: &AppState) -> u64 {
         _ => 8,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::parse_level;
+    use crate::models::Food;
+
+    // A 4x5 board with the snake winding through every cell but the top left
+    // one, its head right next to it and facing it.
+    fn get_full_board_state() -> State {
+        let level = parse_level(r#"{ "name": "Full", "author": "Test", "width": 5, "height": 4, "start_direction": "Left", "edges": "Solid",
+            "map": ["S....", ".....", ".....", "....."] }"#).unwrap();
+        let mut state = crate::init_game_state();
+        let path: Vec<Coord> = (0..level.height)
+            .flat_map(|i| (0..level.width).map(move |j| if i % 2 == 0 { (i, j) } else { (i, level.width - 1 - j) }))
+            .collect();
+
+        state.game_state = GameState::new_seeded(1, &level, Some(1));
+        state.game_state.snake_body = path[1..].to_vec();
+        state.game_state.food = vec![Food { kind: FoodKindsEnum::Normal, position: path[0], expires_in: None }];
+        set_grid_values(&mut state);
+
+        state
+    }
+
+    #[test]
+    fn filling_the_board_wins() {
+        let mut state = get_full_board_state();
+
+        state.game_state.pending_growth = 1;
+        step(&mut state);
+
+        assert!(state.game_state.game_over);
+        assert!(state.game_state.victory);
+        assert_eq!(state.game_state.score, get_food_value(1) + VICTORY_BONUS);
+    }
+
+    #[test]
+    fn head_and_hazard_swapping_cells_is_a_hit() {
+        let level = parse_level(r#"{ "name": "Swap", "author": "Test", "width": 5, "height": 3, "start_direction": "Right", "edges": "Solid",
+            "map": [".S...", ".....", "....."], "hazards": [{ "type": "Patrol", "path": [[0, 2], [0, 1]], "every": 1 }] }"#).unwrap();
+        let mut state = crate::init_game_state();
+
+        state.game_state = GameState::new_seeded(1, &level, Some(1));
+        step(&mut state);
+
+        assert_eq!(state.game_state.snake_body, vec![(0, 2)]);
+        assert_eq!(hazards::get_positions(&state.game_state), vec![(0, 1)]);
+        assert!(state.game_state.game_over);
+        assert!(!state.game_state.victory);
+    }
+
+    #[test]
+    fn snake_rides_over_a_backwards_conveyor() {
+        let level = parse_level(r#"{ "name": "Belt", "author": "Test", "width": 6, "height": 3, "start_direction": "Right", "edges": "Solid",
+            "map": ["......", "S.....", "......"], "tiles": [{ "type": "Conveyor", "cells": [[1, 2], [1, 3]], "direction": "Left" }] }"#).unwrap();
+        let mut state = crate::init_game_state();
+
+        state.game_state = GameState::new_seeded(1, &level, Some(1));
+        state.game_state.snake_body = vec![(1, 2), (1, 1), (1, 0)];
+        set_grid_values(&mut state);
+        step(&mut state);
+
+        assert!(!state.game_state.game_over);
+        assert_eq!(state.game_state.snake_body[0], (1, 3));
+    }
+
+    #[test]
+    fn world_moves_on_while_the_snake_is_stuck() {
+        let level = parse_level(r#"{ "name": "Sticky", "author": "Test", "width": 5, "height": 3, "start_direction": "Right", "edges": "Solid",
+            "map": [".....", "S....", "....."], "hazards": [{ "type": "Patrol", "path": [[0, 1], [0, 2], [0, 3]], "every": 1 }] }"#).unwrap();
+        let mut state = crate::init_game_state();
+
+        state.game_state = GameState::new_seeded(1, &level, Some(1));
+        state.game_state.stuck = true;
+        state.game_state.speed_boost = 5;
+        step(&mut state);
+
+        assert_eq!(state.game_state.snake_body, vec![(1, 0)]);
+        assert_eq!(state.game_state.steps, 1);
+        assert_eq!(hazards::get_positions(&state.game_state), vec![(0, 2)]);
+        assert_eq!(state.game_state.speed_boost, 4);
+
+        step(&mut state);
+
+        assert_eq!(state.game_state.snake_body, vec![(1, 1)]);
+    }
+
+    #[test]
+    fn nothing_spawns_on_a_full_board() {
+        let mut state = get_full_board_state();
+
+        state.game_state.food.clear();
+        state.game_state.snake_body.insert(0, (0, 0));
+        set_grid_values(&mut state);
+
+        assert_eq!(get_spawn_position(&mut state.game_state), None);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::levels::{get_reachable_cells, get_step, validate_level};
+use crate::models::{Coord, DirectionsEnum, EdgesEnum, Level, LevelSource, LevelStylesEnum, FIELD_SIZE, LEVEL_STYLES};
+use crate::utils::get_seeded_int;
+
+const MAX_ATTEMPTS: u64 = 16;
+const MAZE_BLOCK: usize = 3;
+const SAFE_START_CELLS: usize = 3;
+
+pub fn generate_random_level(seed: u64) -> Level {
+    let mut style_seed = seed;
+    let style = LEVEL_STYLES[get_seeded_int(&mut style_seed, 0, LEVEL_STYLES.len())];
+
+    generate_level(seed, style)
+}
+
+// Falls back to an open field, which is always playable, when no attempt gives
+// a playable layout.
+pub fn generate_level(seed: u64, style: LevelStylesEnum) -> Level {
+    try_generate_level(seed, style).unwrap_or_else(|| get_open_level(seed, style))
+}
+
+fn try_generate_level(seed: u64, style: LevelStylesEnum) -> Option<Level> {
+    let (height, width) = (FIELD_SIZE, FIELD_SIZE);
+
+    for attempt in 0..MAX_ATTEMPTS {
+        let mut rng = seed ^ attempt.wrapping_mul(0xD6E8_FEB8_6659_FD93);
+        let grid = match style {
+            LevelStylesEnum::Rooms => gen_rooms(&mut rng, height, width),
+            LevelStylesEnum::Maze => gen_maze(&mut rng, height, width),
+            LevelStylesEnum::Pillars => gen_pillars(&mut rng, height, width),
+            LevelStylesEnum::Symmetric => gen_symmetric(&mut rng, height, width),
+        };
+
+        if let Some(level) = build_level(seed, style, &grid, &mut rng) {
+            return Some(level);
+        }
+    }
+
+    None
+}
+
+fn get_open_level(seed: u64, style: LevelStylesEnum) -> Level {
+    let mut rng = seed;
+
+    build_level(seed, style, &vec![vec![false; FIELD_SIZE]; FIELD_SIZE], &mut rng).expect("an open field is always playable")
+}
+
+pub fn get_style_name(style: LevelStylesEnum) -> &'static str {
+    match style {
+        LevelStylesEnum::Rooms => "Rooms",
+        LevelStylesEnum::Maze => "Maze",
+        LevelStylesEnum::Pillars => "Pillars",
+        LevelStylesEnum::Symmetric => "Symmetric",
+    }
+}
+
+fn build_level(seed: u64, style: LevelStylesEnum, grid: &[Vec<bool>], rng: &mut u64) -> Option<Level> {
+    let mut level = Level {
+        name: format!("{} #{}", get_style_name(style), seed % 10000),
+        author: String::from("Level Generator"),
+        width: grid[0].len(),
+        height: grid.len(),
+        start: (0, 0),
+        start_direction: DirectionsEnum::Right,
+        walls: get_walls(grid),
+        edges: EdgesEnum::Wrap,
+        portals: Vec::new(),
+        portal_digits: Vec::new(),
+        hazards: Vec::new(),
+        food_table: Vec::new(),
+        puzzle_food: Vec::new(),
+        move_limit: None,
+        keys: Vec::new(),
+        doors: Vec::new(),
+        switches: Vec::new(),
+        tiles: Vec::new(),
+        source: Some(LevelSource::Generated { seed, style }),
+    };
+
+    let (start, start_direction) = pick_start(&level, grid, rng)?;
+    let reachable = get_reachable_cells(&level, start, &|(i, j)| !grid[i][j]);
+    let mut connected = vec![vec![true; level.width]; level.height];
+
+    // Pockets the start cannot reach are filled in, so every free cell is reachable.
+    for (i, j) in reachable {
+        connected[i][j] = false;
+    }
+
+    level.walls = get_walls(&connected);
+    level.start = start;
+    level.start_direction = start_direction;
+
+    validate_level(&level).ok().map(|_| level)
+}
+
+fn pick_start(level: &Level, grid: &[Vec<bool>], rng: &mut u64) -> Option<(Coord, DirectionsEnum)> {
+    let directions = [DirectionsEnum::Up, DirectionsEnum::Down, DirectionsEnum::Left, DirectionsEnum::Right];
+    let mut candidates = Vec::new();
+
+    for i in 0..level.height {
+        for j in 0..level.width {
+            for direction in directions {
+                let mut cell = (i, j);
+                let mut is_safe = true;
+
+                for _ in 0..SAFE_START_CELLS {
+                    is_safe &= !grid[cell.0][cell.1];
+
+                    match get_step(level, cell, direction) {
+                        Some(next) => cell = next,
+                        None => is_safe = false,
+                    }
+                }
+
+                if is_safe { candidates.push(((i, j), direction)); }
+            }
+        }
+    }
+
+    if candidates.is_empty() { return None; }
+
+    Some(candidates[get_seeded_int(rng, 0, candidates.len())])
+}
+
+fn get_walls(grid: &[Vec<bool>]) -> Vec<Coord> {
+    let mut walls = Vec::new();
+
+    for (i, row) in grid.iter().enumerate() {
+        for (j, is_wall) in row.iter().enumerate() {
+            if *is_wall { walls.push((i, j)); }
+        }
+    }
+
+    walls
+}
+
+fn gen_rooms(rng: &mut u64, height: usize, width: usize) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; width]; height];
+    let row_dividers = get_dividers(rng, height);
+    let col_dividers = get_dividers(rng, width);
+
+    for (i, row) in grid.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            let is_border = i == 0 || j == 0 || i == height - 1 || j == width - 1;
+
+            *cell = is_border || row_dividers.contains(&i) || col_dividers.contains(&j);
+        }
+    }
+
+    let row_bounds: Vec<usize> = [vec![0], row_dividers.clone(), vec![height - 1]].concat();
+    let col_bounds: Vec<usize> = [vec![0], col_dividers.clone(), vec![width - 1]].concat();
+
+    // Every wall segment between two rooms gets a two cell wide doorway.
+    for col in &col_dividers {
+        for bounds in row_bounds.windows(2) {
+            let door = get_seeded_int(rng, bounds[0] + 1, bounds[1] - 1);
+
+            grid[door][*col] = false;
+            grid[door + 1][*col] = false;
+        }
+    }
+
+    for row in &row_dividers {
+        for bounds in col_bounds.windows(2) {
+            let door = get_seeded_int(rng, bounds[0] + 1, bounds[1] - 1);
+
+            grid[*row][door] = false;
+            grid[*row][door + 1] = false;
+        }
+    }
+
+    grid
+}
+
+fn get_dividers(rng: &mut u64, size: usize) -> Vec<usize> {
+    if get_seeded_int(rng, 0, 2) == 0 {
+        vec![get_seeded_int(rng, size / 3, size - size / 3)]
+    } else {
+        vec![
+            get_seeded_int(rng, 4, size / 2 - 1),
+            get_seeded_int(rng, size / 2 + 2, size - 4),
+        ]
+    }
+}
+
+fn gen_maze(rng: &mut u64, height: usize, width: usize) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; width]; height];
+    let rows = height / MAZE_BLOCK;
+    let cols = width / MAZE_BLOCK;
+
+    // The maze is carved on blocks of 2x2 floor plus one wall line, so corridors
+    // stay two cells wide and there is always room for the bonus.
+    for (i, row) in grid.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = i % MAZE_BLOCK == MAZE_BLOCK - 1 || j % MAZE_BLOCK == MAZE_BLOCK - 1 ||
+                i >= rows * MAZE_BLOCK || j >= cols * MAZE_BLOCK;
+        }
+    }
+
+    let mut visited = vec![vec![false; cols]; rows];
+    let mut stack = vec![(get_seeded_int(rng, 0, rows), get_seeded_int(rng, 0, cols))];
+
+    visited[stack[0].0][stack[0].1] = true;
+
+    while let Some(&(i, j)) = stack.last() {
+        let mut neighbours = Vec::new();
+
+        if i > 0 && !visited[i - 1][j] { neighbours.push((i - 1, j)); }
+        if i + 1 < rows && !visited[i + 1][j] { neighbours.push((i + 1, j)); }
+        if j > 0 && !visited[i][j - 1] { neighbours.push((i, j - 1)); }
+        if j + 1 < cols && !visited[i][j + 1] { neighbours.push((i, j + 1)); }
+
+        if neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = neighbours[get_seeded_int(rng, 0, neighbours.len())];
+
+        open_maze_wall(&mut grid, (i, j), next);
+        visited[next.0][next.1] = true;
+        stack.push(next);
+    }
+
+    // Knock out a few extra walls so the maze has loops instead of only dead ends.
+    for i in 0..rows {
+        for j in 0..cols {
+            if j + 1 < cols && get_seeded_int(rng, 0, 4) == 0 { open_maze_wall(&mut grid, (i, j), (i, j + 1)); }
+            if i + 1 < rows && get_seeded_int(rng, 0, 4) == 0 { open_maze_wall(&mut grid, (i, j), (i + 1, j)); }
+        }
+    }
+
+    grid
+}
+
+fn open_maze_wall(grid: &mut [Vec<bool>], from: Coord, to: Coord) {
+    let (i, j) = (from.0.min(to.0), from.1.min(to.1));
+    let (row, col) = (i * MAZE_BLOCK, j * MAZE_BLOCK);
+
+    if from.0 == to.0 {
+        grid[row][col + MAZE_BLOCK - 1] = false;
+        grid[row + 1][col + MAZE_BLOCK - 1] = false;
+    } else {
+        grid[row + MAZE_BLOCK - 1][col] = false;
+        grid[row + MAZE_BLOCK - 1][col + 1] = false;
+    }
+}
+
+fn gen_pillars(rng: &mut u64, height: usize, width: usize) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; width]; height];
+    let pillars = get_seeded_int(rng, 6, 13);
+    let mut placed = 0;
+
+    for _ in 0..pillars * 4 {
+        let size = get_seeded_int(rng, 1, 3);
+        let (i, j) = (get_seeded_int(rng, 1, height - size), get_seeded_int(rng, 1, width - size));
+
+        // Pillars never touch each other, which keeps the gaps between them open.
+        let is_clear = (i - 1..=i + size).all(|x| (j - 1..=j + size).all(|y| !grid[x][y]));
+
+        if !is_clear { continue; }
+
+        for row in grid.iter_mut().skip(i).take(size) {
+            for cell in row.iter_mut().skip(j).take(size) {
+                *cell = true;
+            }
+        }
+
+        placed += 1;
+
+        if placed == pillars { break; }
+    }
+
+    grid
+}
+
+fn gen_symmetric(rng: &mut u64, height: usize, width: usize) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; width]; height];
+    let segments = get_seeded_int(rng, 3, 7);
+
+    for _ in 0..segments {
+        let length = get_seeded_int(rng, 2, 5);
+        let is_horizontal = get_seeded_int(rng, 0, 2) == 0;
+        let (i, j) = (get_seeded_int(rng, 1, height / 2), get_seeded_int(rng, 1, width / 2));
+
+        for step in 0..length {
+            let (x, y) = if is_horizontal { (i, j + step) } else { (i + step, j) };
+
+            if x >= height / 2 || y >= width / 2 { break; }
+
+            grid[x][y] = true;
+            grid[x][width - 1 - y] = true;
+            grid[height - 1 - x][y] = true;
+            grid[height - 1 - x][width - 1 - y] = true;
+        }
+    }
+
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_levels_are_playable_and_repeatable() {
+        for seed in (0..40).chain([u64::MAX, 0xDEAD_BEEF]) {
+            for style in LEVEL_STYLES {
+                let level = try_generate_level(seed, style).unwrap_or_else(|| panic!("{:?} {} gave up", style, seed));
+
+                assert_eq!(validate_level(&level), Ok(()), "{:?} {}", style, seed);
+                assert_eq!(generate_level(seed, style), level, "{:?} {}", style, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn open_field_fallback_is_playable() {
+        for style in LEVEL_STYLES {
+            assert_eq!(validate_level(&get_open_level(7, style)), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::levels::{get_neighbour, get_portal_index};
+use crate::models::{Coord, DirectionsEnum, GameState, Hazard, HazardDefinition, Level};
+
+pub fn create_hazards(level: &Level) -> Vec<Hazard> {
+    level.hazards
+        .iter()
+        .map(|definition| match definition {
+            HazardDefinition::Patrol { path, every } => Hazard {
+                position: path[0],
+                path: path.clone(),
+                path_index: 0,
+                reversed: false,
+                direction: DirectionsEnum::Right,
+                every: *every,
+            },
+            HazardDefinition::Bounce { start, direction, every } => Hazard {
+                position: *start,
+                path: Vec::new(),
+                path_index: 0,
+                reversed: false,
+                direction: *direction,
+                every: *every,
+            },
+        })
+        .collect()
+}
+
+pub fn move_hazards(game_state: &mut GameState) {
+    let steps = game_state.steps;
+    let level = &game_state.level;
+
+    for hazard in game_state.hazards.iter_mut() {
+        if steps.is_multiple_of(hazard.every) {
+            *hazard = get_moved_hazard(level, hazard);
+        }
+    }
+}
+
+// Cells the hazards occupy now and after the next step, where food must not spawn.
+pub fn get_hazard_zone(game_state: &GameState) -> Vec<Coord> {
+    let next_step = game_state.steps + 1;
+
+    game_state.hazards
+        .iter()
+        .flat_map(|hazard| {
+            let next = if next_step.is_multiple_of(hazard.every) { get_moved_hazard(&game_state.level, hazard) } else { hazard.clone() };
+
+            [hazard.position, next.position]
+        })
+        .collect()
+}
+
+pub fn get_positions(game_state: &GameState) -> Vec<Coord> {
+    game_state.hazards.iter().map(|hazard| hazard.position).collect()
+}
+
+// True when a hazard moved from `to` into `from` on the step the head moved
+// from `from` to `to`, so the two passed through each other.
+pub fn is_crossed(game_state: &GameState, previous: &[Coord], from: Coord, to: Coord) -> bool {
+    game_state.hazards.iter().zip(previous).any(|(hazard, position)| *position == to && hazard.position == from)
+}
+
+pub fn is_hazard(game_state: &GameState, coord: Coord) -> bool {
+    game_state.hazards.iter().any(|hazard| hazard.position == coord)
+}
+
+// Every cell a hazard can ever reach, used by the validator to keep the start clear.
+pub fn get_hazard_cells(level: &Level, definition: &HazardDefinition) -> Vec<Coord> {
+    match definition {
+        HazardDefinition::Patrol { path, .. } => path.clone(),
+        HazardDefinition::Bounce { start, direction, .. } => {
+            let mut cells = vec![*start];
+
+            for direction in [*direction, get_opposite_direction(*direction)] {
+                let mut cell = *start;
+
+                while let Some(next) = get_neighbour(level, cell, direction) {
+                    if is_blocked(level, next) || cells.contains(&next) { break; }
+
+                    cells.push(next);
+                    cell = next;
+                }
+            }
+
+            cells
+        }
+    }
+}
+
+pub fn get_opposite_direction(direction: DirectionsEnum) -> DirectionsEnum {
+    match direction {
+        DirectionsEnum::Up => DirectionsEnum::Down,
+        DirectionsEnum::Down => DirectionsEnum::Up,
+        DirectionsEnum::Left => DirectionsEnum::Right,
+        DirectionsEnum::Right => DirectionsEnum::Left,
+    }
+}
+
+fn get_moved_hazard(level: &Level, hazard: &Hazard) -> Hazard {
+    let mut moved = hazard.clone();
+
+    if !hazard.path.is_empty() {
+        // Patrols walk their path to the end and then walk it back.
+        let last = hazard.path.len() - 1;
+
+        if last == 0 { return moved; }
+
+        if hazard.path_index == last { moved.reversed = true; }
+        if hazard.path_index == 0 { moved.reversed = false; }
+
+        moved.path_index = if moved.reversed { hazard.path_index - 1 } else { hazard.path_index + 1 };
+        moved.position = hazard.path[moved.path_index];
+
+        return moved;
+    }
+
+    // Bouncing hazards reverse when they run into a wall, a portal or a solid edge.
+    for direction in [hazard.direction, get_opposite_direction(hazard.direction)] {
+        if let Some(next) = get_neighbour(level, hazard.position, direction).filter(|next| !is_blocked(level, *next)) {
+            moved.position = next;
+            moved.direction = direction;
+
+            return moved;
+        }
+    }
+
+    moved
+}
+
+fn is_blocked(level: &Level, coord: Coord) -> bool {
+    level.walls.contains(&coord) || get_portal_index(level, coord).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::parse_level;
+
+    fn parse_hazard_level(map: &[&str], hazard: &str) -> Level {
+        let content = format!(
+            r#"{{ "name": "Test", "author": "Test", "width": {}, "height": {}, "start_direction": "Down", "edges": "Solid", "map": {:?}, "hazards": [{}] }}"#,
+            map[0].len(), map.len(), map, hazard,
+        );
+
+        parse_level(&content).unwrap()
+    }
+
+    fn get_positions_after(level: &Level, moves: usize) -> Vec<Coord> {
+        let mut hazard = create_hazards(level).remove(0);
+
+        (0..moves)
+            .map(|_| {
+                hazard = get_moved_hazard(level, &hazard);
+                hazard.position
+            })
+            .collect()
+    }
+
+    #[test]
+    fn patrol_walks_its_path_back_from_the_last_waypoint() {
+        let level = parse_hazard_level(&["S....", "....."], r#"{ "type": "Patrol", "path": [[1, 1], [1, 2], [1, 3]], "every": 1 }"#);
+
+        assert_eq!(get_positions_after(&level, 5), vec![(1, 2), (1, 3), (1, 2), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn bouncer_turns_around_at_a_wall() {
+        let level = parse_hazard_level(&["S....", "...#."], r#"{ "type": "Bounce", "start": [1, 1], "direction": "Right", "every": 1 }"#);
+
+        assert_eq!(get_positions_after(&level, 4), vec![(1, 2), (1, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn bouncer_turns_around_at_a_solid_edge() {
+        let level = parse_hazard_level(&["S....", "....."], r#"{ "type": "Bounce", "start": [1, 3], "direction": "Right", "every": 1 }"#);
+
+        assert_eq!(get_positions_after(&level, 3), vec![(1, 4), (1, 3), (1, 2)]);
+    }
+}
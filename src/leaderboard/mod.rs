@@ -1,20 +1,31 @@
 use std::fs;
 use crossterm::event::{KeyCode, KeyEvent};
-use crate::models::{AppState, ScreensEnum, State, SCREEN_SIZE};
-use crate::utils::{print_empty_lines, set_col_position};
+use crate::daily::get_streak;
+use crate::modifiers::{format_tags, parse_tags};
+use crate::models::{AppState, GameModesEnum, LeaderboardEntry, ScreensEnum, State, LEADERBOARD_CATEGORIES, SCREEN_SIZE};
+use crate::utils::{format_date, get_today, out, outln, parse_date, print_empty_lines, set_col_position};
 
 pub fn process_keyboard_event(state: &mut State, key_event: KeyEvent) {
     let app_state = &mut state.app_state;
 
     match key_event.code {
         KeyCode::Esc => back_to_menu(app_state),
+        KeyCode::Left => switch_category(app_state, false),
+        KeyCode::Right => switch_category(app_state, true),
         _ => {}
     }
 }
 
 pub fn render(state: &mut State) {
     let app_state = &mut state.app_state;
-    let leaderboard = app_state.leaderboard.get_or_insert_with(get_leaderboard);
+    let mode = app_state.leaderboard_mode;
+    let leaderboard: Vec<LeaderboardEntry> = app_state.leaderboard
+        .get_or_insert_with(get_leaderboard)
+        .iter()
+        .filter(|entry| entry.mode == mode)
+        .cloned()
+        .collect();
+    let category = format!("< {} >", get_category_name(mode));
     let title_offset = app_state.view_offset.0 + (SCREEN_SIZE.0 - 12) / 2;
     let no_records_instructions = [
         "There are no records set..",
@@ -22,15 +33,18 @@ pub fn render(state: &mut State) {
     ];
 
     set_col_position(title_offset);
-    print!("LEADERBOARD");
+    out!("LEADERBOARD");
+    outln!();
+    set_col_position(app_state.view_offset.0 + SCREEN_SIZE.0.saturating_sub(category.len() as u16) / 2);
+    out!("{}", category);
 
-    print_empty_lines(3);
+    print_empty_lines(2);
 
-    if leaderboard.len() == 0 {
+    if leaderboard.is_empty() {
         for instruction in no_records_instructions {
             set_col_position(app_state.view_offset.0);
-            print!("{}", instruction);
-            println!();
+            out!("{}", instruction);
+            outln!();
         }
 
         return;
@@ -39,55 +53,149 @@ pub fn render(state: &mut State) {
     for i in 0..10 {
         let score = leaderboard
             .get(i)
-            .map(|s| s.to_string())
+            .map(format_score)
             .unwrap_or(" ".to_string());
 
         set_col_position(app_state.view_offset.0);
-        print!("{}. {}", i + 1, score);
-        println!();
+        out!("{}. {}", i + 1, score);
+        outln!();
     }
+
+    outln!();
+    set_col_position(app_state.view_offset.0);
+    out!("Games played: {}   Victories: {}", app_state.stats.games_played, app_state.stats.victories);
+    outln!();
+    set_col_position(app_state.view_offset.0);
+    out!("Daily streak: {}", get_streak(&app_state.stats, get_today()));
+    outln!();
 }
 
-pub fn check_if_new_record(state: &mut State, score: u64) {
-    let mut top_scores = get_leaderboard();
+// Every game mode keeps its own top 10 in the same file.
+pub fn check_if_new_record(state: &mut State, entry: LeaderboardEntry) {
+    let mut records = get_leaderboard();
+    let (mut top_scores, others): (Vec<LeaderboardEntry>, Vec<LeaderboardEntry>) = records
+        .drain(..)
+        .partition(|record| record.mode == entry.mode);
     let is_new_record = match top_scores.last() {
         None => true,
-        Some(last) => top_scores.len() < 10 || score > *last,
+        Some(last) => top_scores.len() < 10 || entry.score > last.score,
     };
 
-    if !is_new_record || score == 0 || top_scores.contains(&score) { return; }
+    if !is_new_record || entry.score == 0 || top_scores.contains(&entry) { return; }
 
-    top_scores.push(score);
-    top_scores.sort_by(|a, b| b.cmp(a));
+    top_scores.push(entry);
+    top_scores.sort_by_key(|entry| std::cmp::Reverse(entry.score));
     top_scores.truncate(10);
+    top_scores.extend(others);
 
     save_new_leaderboard(&top_scores);
     state.app_state.leaderboard = Some(top_scores);
 }
 
+fn switch_category(app_state: &mut AppState, is_next: bool) {
+    let count = LEADERBOARD_CATEGORIES.len();
+    let index = LEADERBOARD_CATEGORIES
+        .iter()
+        .position(|item| item.value == app_state.leaderboard_mode)
+        .unwrap_or(0);
+    let index = if is_next { (index + 1) % count } else { (index + count - 1) % count };
+
+    app_state.leaderboard_mode = LEADERBOARD_CATEGORIES[index].value;
+    app_state.screen_changed = true;
+}
+
 fn back_to_menu(app_state: &mut AppState) {
     app_state.selected_screen = ScreensEnum::Menu;
     app_state.screen_changed = true;
 }
 
-fn get_leaderboard() -> Vec<u64> {
+fn get_leaderboard() -> Vec<LeaderboardEntry> {
     let path = "leaderboard.txt";
     let content = fs::read_to_string(path).unwrap();
-    let mut records: Vec<u64> = content
+    let mut records: Vec<LeaderboardEntry> = content
         .lines()
-        .filter_map(|line| line.trim().parse::<u64>().ok())
+        .filter_map(parse_entry)
         .collect();
 
-    records.sort_by(|a, b| b.cmp(a));
+    records.sort_by_key(|entry| std::cmp::Reverse(entry.score));
 
     records
 }
 
-fn save_new_leaderboard(records: &Vec<u64>) {
+fn get_category_name(mode: GameModesEnum) -> &'static str {
+    LEADERBOARD_CATEGORIES.iter().find(|item| item.value == mode).map(|item| item.label).unwrap_or("")
+}
+
+fn format_score(entry: &LeaderboardEntry) -> String {
+    let mut score = entry.score.to_string();
+
+    if let Some(day) = entry.day { score.push_str(&format!("  {}", format_date(day))); }
+    if entry.victory { score.push_str("  WIN"); }
+    if !entry.modifiers.is_empty() { score.push_str(&format!("  +{}", format_tags(&entry.modifiers))); }
+
+    score
+}
+
+// A line is the score, followed by "W" when the game was won, by the game mode
+// for anything other than Classic, by the date for daily challenges and by the
+// modifier tags after a "+".
+fn parse_entry(line: &str) -> Option<LeaderboardEntry> {
+    let mut parts = line.split_whitespace();
+    let score = parts.next()?.parse::<u64>().ok()?;
+    let rest: Vec<&str> = parts.collect();
+    let victory = rest.contains(&"W");
+    let day = rest.iter().find_map(|part| parse_date(part));
+    let modifiers = rest.iter()
+        .find_map(|part| part.strip_prefix('+').and_then(parse_tags))
+        .unwrap_or_default();
+    let mode = LEADERBOARD_CATEGORIES
+        .iter()
+        .map(|item| item.value)
+        .find(|mode| get_mode_tag(*mode).is_some_and(|tag| rest.contains(&tag)))
+        .unwrap_or(GameModesEnum::Classic);
+
+    Some(LeaderboardEntry { score, victory, mode, day, modifiers })
+}
+
+fn format_entry(entry: &LeaderboardEntry) -> String {
+    let mut line = entry.score.to_string();
+
+    if entry.victory { line.push_str(" W"); }
+
+    if let Some(tag) = get_mode_tag(entry.mode) {
+        line.push(' ');
+        line.push_str(tag);
+    }
+
+    if let Some(day) = entry.day {
+        line.push(' ');
+        line.push_str(&format_date(day));
+    }
+
+    if !entry.modifiers.is_empty() {
+        line.push_str(" +");
+        line.push_str(&format_tags(&entry.modifiers));
+    }
+
+    line
+}
+
+// Unranked games are never recorded, so their modes have no tag.
+fn get_mode_tag(mode: GameModesEnum) -> Option<&'static str> {
+    match mode {
+        GameModesEnum::Classic | GameModesEnum::Zen | GameModesEnum::Puzzle => None,
+        GameModesEnum::TimeAttack => Some("TA"),
+        GameModesEnum::Survival => Some("SV"),
+        GameModesEnum::Daily => Some("DC"),
+        GameModesEnum::Fog => Some("FG"),
+    }
+}
+
+fn save_new_leaderboard(records: &[LeaderboardEntry]) {
     let path = "leaderboard.txt";
     let content = records
         .iter()
-        .map(|n| n.to_string())
+        .map(format_entry)
         .collect::<Vec<String>>().join("\n");
 
     fs::write(path, content).unwrap();
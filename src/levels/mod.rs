@@ -0,0 +1,711 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use crate::generator::generate_random_level;
+use crate::hazards::get_hazard_cells;
+use crate::tiles::get_tile_cells;
+use crate::models::{AppState, Coord, DirectionsEnum, EdgesEnum, HazardDefinition, Level, LevelFile, LevelSource, TileDefinition, LEVELS_DIR, MAX_WORLD_SIZE};
+use crate::utils::get_short_seed;
+
+pub const WALL_CHAR: char = '#';
+pub const FLOOR_CHAR: char = '.';
+pub const START_CHAR: char = 'S';
+pub const KEY_CHAR: char = 'K';
+pub const DOOR_CHAR: char = 'D';
+pub const MAX_PORTAL_PAIRS: usize = 10;
+pub const MIN_FREE_CELLS: usize = 10;
+pub const MAX_PUZZLE_FOOD: usize = 26;
+
+static BUILT_IN_LEVELS: [&str; 8] = [
+    include_str!("../../assets/levels/plain_field.json"),
+    include_str!("../../assets/levels/box.json"),
+    include_str!("../../assets/levels/labyrinth.json"),
+    include_str!("../../assets/levels/two_sides.json"),
+    include_str!("../../assets/levels/roundabout.json"),
+    include_str!("../../assets/levels/open_world.json"),
+    include_str!("../../assets/levels/vault.json"),
+    include_str!("../../assets/levels/ice_rink.json"),
+];
+
+static BUILT_IN_PUZZLES: [&str; 3] = [
+    include_str!("../../assets/puzzles/first_steps.json"),
+    include_str!("../../assets/puzzles/zigzag.json"),
+    include_str!("../../assets/puzzles/corners.json"),
+];
+
+impl Default for Level {
+    fn default() -> Self {
+        parse_level(BUILT_IN_LEVELS[0]).unwrap()
+    }
+}
+
+pub fn load_levels() -> (Vec<Level>, Vec<String>) {
+    let mut levels: Vec<Level> = BUILT_IN_LEVELS
+        .iter()
+        .enumerate()
+        .map(|(index, content)| Level { source: Some(LevelSource::BuiltIn(index)), ..parse_level(content).unwrap() })
+        .collect();
+
+    let (custom_levels, errors) = load_custom_levels();
+
+    levels.extend(custom_levels.into_iter().filter(|level| !is_puzzle(level)));
+
+    (levels, errors)
+}
+
+// Built-in puzzles first, then the custom levels that set a move limit. Broken
+// files are already reported by `load_levels`.
+pub fn load_puzzles() -> Vec<Level> {
+    let mut puzzles: Vec<Level> = BUILT_IN_PUZZLES
+        .iter()
+        .map(|content| parse_level(content).unwrap())
+        .collect();
+
+    puzzles.extend(load_custom_levels().0.into_iter().filter(is_puzzle));
+
+    puzzles
+}
+
+pub fn is_puzzle(level: &Level) -> bool {
+    level.move_limit.is_some()
+}
+
+// The entry right after the loaded levels is "Random Level", which generates a
+// fresh layout every time it is picked.
+pub fn get_selected_level(app_state: &AppState) -> Level {
+    match app_state.levels.get(app_state.level as usize) {
+        Some(level) => level.clone(),
+        None => generate_random_level(get_short_seed()),
+    }
+}
+
+pub fn load_level_file(path: &Path) -> Result<Level, Vec<String>> {
+    let content = fs::read_to_string(path).map_err(|e| vec![format!("cannot read {}: {}", path.display(), e)])?;
+    let level = parse_level(&content).map_err(|e| vec![e])?;
+
+    validate_level(&level)?;
+
+    Ok(level)
+}
+
+pub fn parse_level(content: &str) -> Result<Level, String> {
+    let file: LevelFile = serde_json::from_str(content).map_err(|e| format!("invalid level file: {}", e))?;
+
+    level_from_file(&file)
+}
+
+pub fn validate_level(level: &Level) -> Result<(), Vec<String>> {
+    let grid = get_blocked_grid(level);
+    let is_free = |(i, j): Coord| !grid[i][j];
+    let free_cells = grid.iter().flatten().filter(|blocked| !**blocked).count();
+    let mut issues = Vec::new();
+
+    if level.walls.contains(&level.start) {
+        issues.push(format!("start cell {} is a wall", format_coord(level.start)));
+    } else if get_portal_exit(level, level.start).is_some() {
+        issues.push(format!("start cell {} is a portal", format_coord(level.start)));
+    } else {
+        match get_step(level, level.start, level.start_direction) {
+            None => issues.push(format!("start cell {} faces straight into a solid edge", format_coord(level.start))),
+            Some(next) if !is_free(next) => issues.push(format!("start cell {} faces straight into a wall", format_coord(level.start))),
+            Some(_) => {}
+        }
+    }
+
+    if free_cells < MIN_FREE_CELLS {
+        issues.push(format!("level has {} free cells, at least {} are required", free_cells, MIN_FREE_CELLS));
+    }
+
+    if is_free(level.start) {
+        let reachable = get_reachable_cells(level, level.start, &is_free);
+
+        if reachable.len() < free_cells {
+            let unreachable = free_cells - reachable.len();
+            issues.push(format!("{} free cells cannot be reached from the start", unreachable));
+        }
+    }
+
+    if is_puzzle(level) {
+        issues.extend(validate_puzzle(level));
+    } else if !level.puzzle_food.is_empty() {
+        issues.push(String::from("level has puzzle food but no move limit"));
+    } else if !has_free_square(&grid) {
+        issues.push(String::from("level has no free 2x2 area for the bonus"));
+    }
+
+    for (index, hazard) in level.hazards.iter().enumerate() {
+        issues.extend(validate_hazard(level, &grid, index + 1, hazard));
+    }
+
+    issues.extend(validate_triggers(level, &grid));
+    issues.extend(validate_tiles(level, &grid));
+
+    if issues.is_empty() { Ok(()) } else { Err(issues) }
+}
+
+fn validate_puzzle(level: &Level) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    if level.puzzle_food.is_empty() {
+        issues.push(String::from("puzzle has no food, mark it with 'a', 'b', 'c' and so on"));
+    }
+
+    if level.move_limit == Some(0) {
+        issues.push(String::from("puzzle move limit must be at least 1"));
+    }
+
+    if let Some(food) = level.puzzle_food.iter().find(|food| level.hazards.iter().any(|hazard| get_hazard_cells(level, hazard).contains(food))) {
+        issues.push(format!("puzzle food at {} is in a hazard's way", format_coord(*food)));
+    }
+
+    issues
+}
+
+// Keys, doors, switches and gates need free cells of their own, away from the
+// start and from hazards. Gates belong to a single switch.
+fn validate_triggers(level: &Level, grid: &[Vec<bool>]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let mut cells: Vec<(Coord, &str)> = Vec::new();
+
+    cells.extend(level.keys.iter().map(|key| (*key, "key")));
+    cells.extend(level.doors.iter().map(|door| (*door, "door")));
+
+    for switch in &level.switches {
+        cells.push((switch.position, "switch"));
+        cells.extend(switch.gates.iter().map(|gate| (*gate, "gate")));
+    }
+
+    if level.doors.len() > level.keys.len() {
+        issues.push(format!("level has {} doors but only {} keys", level.doors.len(), level.keys.len()));
+    }
+
+    if let Some(index) = level.switches.iter().position(|switch| switch.gates.is_empty()) {
+        issues.push(format!("switch {} has no gates", index + 1));
+    }
+
+    let hazard_cells: Vec<Coord> = level.hazards.iter().flat_map(|hazard| get_hazard_cells(level, hazard)).collect();
+    let start_ahead = get_step(level, level.start, level.start_direction);
+
+    for (index, (cell, name)) in cells.iter().enumerate() {
+        if cell.0 >= level.height || cell.1 >= level.width {
+            issues.push(format!("{} at {} is outside the board", name, format_coord(*cell)));
+        } else if grid[cell.0][cell.1] {
+            issues.push(format!("{} at {} is on a wall or a portal", name, format_coord(*cell)));
+        } else if *cell == level.start || Some(*cell) == start_ahead {
+            issues.push(format!("{} at {} is on the start cell or the cell in front of it", name, format_coord(*cell)));
+        } else if hazard_cells.contains(cell) {
+            issues.push(format!("{} at {} is in a hazard's way", name, format_coord(*cell)));
+        } else if cells[..index].iter().any(|(other, _)| other == cell) {
+            issues.push(format!("{} at {} shares its cell with something else", name, format_coord(*cell)));
+        }
+    }
+
+    if issues.is_empty() {
+        issues.extend(validate_trigger_order(level, grid));
+    }
+
+    issues
+}
+
+// Works out what the snake can get to from the start: each key it reaches
+// opens one more door next to the area it can reach, and each switch it
+// reaches opens its gates, until nothing changes. Keys, doors and switches
+// left out are sealed off behind the doors or gates meant to let the snake in.
+fn validate_trigger_order(level: &Level, grid: &[Vec<bool>]) -> Vec<String> {
+    let directions = [DirectionsEnum::Up, DirectionsEnum::Down, DirectionsEnum::Left, DirectionsEnum::Right];
+    let mut blocked = grid.to_vec();
+    let mut issues = Vec::new();
+
+    for (i, j) in level.doors.iter().chain(level.switches.iter().filter(|switch| !switch.open).flat_map(|switch| &switch.gates)) {
+        blocked[*i][*j] = true;
+    }
+
+    let mut collected = vec![false; level.keys.len()];
+    let mut opened = vec![false; level.doors.len()];
+    let mut usable = vec![false; level.switches.len()];
+
+    loop {
+        let reachable = get_reachable_cells(level, level.start, &|(i, j)| !blocked[i][j]);
+        let mut changed = false;
+
+        for (index, key) in level.keys.iter().enumerate() {
+            if collected[index] || !reachable.contains(key) { continue; }
+
+            collected[index] = true;
+            changed = true;
+        }
+
+        for (index, switch) in level.switches.iter().enumerate() {
+            if usable[index] || !reachable.contains(&switch.position) { continue; }
+
+            usable[index] = true;
+            changed = true;
+
+            for (i, j) in &switch.gates {
+                blocked[*i][*j] = false;
+            }
+        }
+
+        let keys_left = collected.iter().filter(|collected| **collected).count() - opened.iter().filter(|opened| **opened).count();
+        let next_door = (0..level.doors.len()).find(|index| {
+            !opened[*index] && reachable.iter().any(|cell| directions.iter().any(|direction| get_step(level, *cell, *direction) == Some(level.doors[*index])))
+        });
+
+        if let Some(index) = next_door.filter(|_| keys_left > 0) {
+            let (i, j) = level.doors[index];
+
+            opened[index] = true;
+            blocked[i][j] = false;
+            changed = true;
+        }
+
+        if !changed { break; }
+    }
+
+    for (key, _) in level.keys.iter().zip(&collected).filter(|(_, collected)| !**collected) {
+        issues.push(format!("key at {} cannot be reached", format_coord(*key)));
+    }
+
+    for (door, _) in level.doors.iter().zip(&opened).filter(|(_, opened)| !**opened) {
+        issues.push(format!("door at {} cannot be reached with a key in hand", format_coord(*door)));
+    }
+
+    for (switch, _) in level.switches.iter().zip(&usable).filter(|(_, usable)| !**usable) {
+        issues.push(format!("switch at {} cannot be reached", format_coord(switch.position)));
+    }
+
+    issues
+}
+
+// Tiles go on free cells other than the start, one tile per cell, and a
+// conveyor may not push the snake into a wall, a portal or a solid edge.
+fn validate_tiles(level: &Level, grid: &[Vec<bool>]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let mut seen: Vec<Coord> = Vec::new();
+
+    for (index, tile) in level.tiles.iter().enumerate() {
+        let cells = get_tile_cells(tile);
+
+        if cells.is_empty() {
+            issues.push(format!("tile group {} has no cells", index + 1));
+        }
+
+        for cell in cells {
+            if cell.0 >= level.height || cell.1 >= level.width {
+                issues.push(format!("tile at {} is outside the board", format_coord(*cell)));
+                continue;
+            }
+
+            if grid[cell.0][cell.1] {
+                issues.push(format!("tile at {} is on a wall or a portal", format_coord(*cell)));
+            } else if *cell == level.start {
+                issues.push(format!("tile at {} is on the start cell", format_coord(*cell)));
+            } else if seen.contains(cell) {
+                issues.push(format!("tile at {} is listed more than once", format_coord(*cell)));
+            } else if let TileDefinition::Conveyor { direction, .. } = tile {
+                if get_step(level, *cell, *direction).is_none_or(|(i, j)| grid[i][j]) {
+                    issues.push(format!("conveyor at {} pushes into a wall or an edge", format_coord(*cell)));
+                }
+            }
+
+            seen.push(*cell);
+        }
+    }
+
+    issues
+}
+
+fn validate_hazard(level: &Level, grid: &[Vec<bool>], number: usize, hazard: &HazardDefinition) -> Vec<String> {
+    let (cells, every) = match hazard {
+        HazardDefinition::Patrol { path, every } => (path.clone(), *every),
+        HazardDefinition::Bounce { start, every, .. } => (vec![*start], *every),
+    };
+
+    if every == 0 {
+        return vec![format!("hazard {} must move at least every 1 step", number)];
+    }
+
+    if cells.is_empty() {
+        return vec![format!("hazard {} has an empty patrol path", number)];
+    }
+
+    if let Some(cell) = cells.iter().find(|(i, j)| *i >= level.height || *j >= level.width || grid[*i][*j]) {
+        return vec![format!("hazard {} is placed on a blocked cell {}", number, format_coord(*cell))];
+    }
+
+    let directions = [DirectionsEnum::Up, DirectionsEnum::Down, DirectionsEnum::Left, DirectionsEnum::Right];
+    let is_connected = cells.windows(2).all(|pair| {
+        directions.iter().any(|direction| get_neighbour(level, pair[0], *direction) == Some(pair[1]))
+    });
+
+    if !is_connected {
+        return vec![format!("hazard {} patrol path has cells that are not next to each other", number)];
+    }
+
+    let reach = get_hazard_cells(level, hazard);
+    let start_ahead = get_step(level, level.start, level.start_direction);
+
+    if reach.contains(&level.start) || start_ahead.is_some_and(|cell| reach.contains(&cell)) {
+        return vec![format!("hazard {} crosses the start cell or the cell in front of it", number)];
+    }
+
+    Vec::new()
+}
+
+pub fn get_reachable_cells(level: &Level, from: Coord, is_free: &dyn Fn(Coord) -> bool) -> Vec<Coord> {
+    let directions = [DirectionsEnum::Up, DirectionsEnum::Down, DirectionsEnum::Left, DirectionsEnum::Right];
+    let mut visited = vec![vec![false; level.width]; level.height];
+    let mut queue = VecDeque::from([from]);
+    let mut reachable = Vec::new();
+
+    visited[from.0][from.1] = true;
+
+    while let Some(cell) = queue.pop_front() {
+        reachable.push(cell);
+
+        for direction in directions {
+            let Some(next) = get_step(level, cell, direction) else { continue; };
+
+            if visited[next.0][next.1] || !is_free(next) { continue; }
+
+            visited[next.0][next.1] = true;
+            queue.push_back(next);
+        }
+    }
+
+    reachable
+}
+
+// A single move of the snake: like `get_neighbour`, but entering a portal continues
+// out of its partner in the same direction.
+pub fn get_step(level: &Level, coord: Coord, direction: DirectionsEnum) -> Option<Coord> {
+    let mut next = get_neighbour(level, coord, direction)?;
+
+    for _ in 0..=level.portals.len() {
+        let Some(exit) = get_portal_exit(level, next) else { return Some(next); };
+
+        next = get_neighbour(level, exit, direction)?;
+    }
+
+    None
+}
+
+pub fn get_portal_exit(level: &Level, coord: Coord) -> Option<Coord> {
+    let (a, b) = level.portals[get_portal_index(level, coord)?];
+
+    if a == coord { Some(b) } else { Some(a) }
+}
+
+pub fn get_portal_index(level: &Level, coord: Coord) -> Option<usize> {
+    level.portals.iter().position(|(a, b)| *a == coord || *b == coord)
+}
+
+// Returns `None` when the move would cross an edge that the level declares solid.
+pub fn get_neighbour(level: &Level, coord: Coord, direction: DirectionsEnum) -> Option<Coord> {
+    let max_row = level.height - 1;
+    let max_col = level.width - 1;
+    let wraps_rows = matches!(level.edges, EdgesEnum::Wrap | EdgesEnum::WrapVertical);
+    let wraps_cols = matches!(level.edges, EdgesEnum::Wrap | EdgesEnum::WrapHorizontal);
+    let (mut curr_row, mut curr_col) = coord;
+
+    match direction {
+        DirectionsEnum::Up if curr_row == 0 => {
+            if !wraps_rows { return None; }
+            curr_row = max_row;
+        }
+        DirectionsEnum::Down if curr_row == max_row => {
+            if !wraps_rows { return None; }
+            curr_row = 0;
+        }
+        DirectionsEnum::Left if curr_col == 0 => {
+            if !wraps_cols { return None; }
+            curr_col = max_col;
+        }
+        DirectionsEnum::Right if curr_col == max_col => {
+            if !wraps_cols { return None; }
+            curr_col = 0;
+        }
+        DirectionsEnum::Up => curr_row -= 1,
+        DirectionsEnum::Down => curr_row += 1,
+        DirectionsEnum::Left => curr_col -= 1,
+        DirectionsEnum::Right => curr_col += 1,
+    }
+
+    Some((curr_row, curr_col))
+}
+
+pub fn get_edges_name(edges: EdgesEnum) -> &'static str {
+    match edges {
+        EdgesEnum::Wrap => "wrap",
+        EdgesEnum::Solid => "solid",
+        EdgesEnum::WrapHorizontal => "wrap h",
+        EdgesEnum::WrapVertical => "wrap v",
+    }
+}
+
+pub fn format_coord(coord: Coord) -> String {
+    format!("(row {}, column {})", coord.0 + 1, coord.1 + 1)
+}
+
+fn get_blocked_grid(level: &Level) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; level.width]; level.height];
+
+    for (x, y) in &level.walls {
+        grid[*x][*y] = true;
+    }
+
+    for (a, b) in &level.portals {
+        grid[a.0][a.1] = true;
+        grid[b.0][b.1] = true;
+    }
+
+    grid
+}
+
+fn has_free_square(grid: &[Vec<bool>]) -> bool {
+    grid.windows(2).any(|rows| {
+        (0..rows[0].len().saturating_sub(1)).any(|j| {
+            !rows[0][j] && !rows[0][j + 1] && !rows[1][j] && !rows[1][j + 1]
+        })
+    })
+}
+
+fn load_custom_levels() -> (Vec<Level>, Vec<String>) {
+    let Ok(entries) = fs::read_dir(LEVELS_DIR) else { return (Vec::new(), Vec::new()); };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+
+    paths.sort();
+
+    let mut levels = Vec::new();
+    let mut errors = Vec::new();
+
+    for path in paths {
+        match load_level_file(&path) {
+            Ok(level) => levels.push(level),
+            Err(issues) => errors.push(format!("{}: {}", path.display(), issues.join("; "))),
+        }
+    }
+
+    (levels, errors)
+}
+
+pub fn get_portal_char(digit: usize) -> char {
+    char::from(b'0' + digit as u8)
+}
+
+// Levels saved before the digits were kept number their pairs in order.
+pub fn get_portal_digit(level: &Level, index: usize) -> usize {
+    level.portal_digits.get(index).copied().unwrap_or(index)
+}
+
+pub fn get_puzzle_food_char(index: usize) -> char {
+    char::from(b'a' + index as u8)
+}
+
+pub fn level_to_file(level: &Level) -> LevelFile {
+    let mut map = vec![vec![FLOOR_CHAR; level.width]; level.height];
+
+    for (x, y) in &level.walls {
+        map[*x][*y] = WALL_CHAR;
+    }
+
+    for (index, (a, b)) in level.portals.iter().enumerate() {
+        let symbol = get_portal_char(get_portal_digit(level, index));
+
+        map[a.0][a.1] = symbol;
+        map[b.0][b.1] = symbol;
+    }
+
+    for (index, (x, y)) in level.puzzle_food.iter().enumerate() {
+        map[*x][*y] = get_puzzle_food_char(index);
+    }
+
+    for (x, y) in &level.keys {
+        map[*x][*y] = KEY_CHAR;
+    }
+
+    for (x, y) in &level.doors {
+        map[*x][*y] = DOOR_CHAR;
+    }
+
+    map[level.start.0][level.start.1] = START_CHAR;
+
+    LevelFile {
+        name: level.name.clone(),
+        author: level.author.clone(),
+        width: level.width,
+        height: level.height,
+        start_direction: level.start_direction,
+        edges: level.edges,
+        hazards: level.hazards.clone(),
+        food: level.food_table.clone(),
+        moves: level.move_limit,
+        switches: level.switches.clone(),
+        tiles: level.tiles.clone(),
+        map: map.into_iter().map(|row| row.into_iter().collect()).collect(),
+    }
+}
+
+fn level_from_file(file: &LevelFile) -> Result<Level, String> {
+    if file.width == 0 || file.height == 0 || file.width > MAX_WORLD_SIZE || file.height > MAX_WORLD_SIZE {
+        return Err(format!("board size must be between 1x1 and {}x{}", MAX_WORLD_SIZE, MAX_WORLD_SIZE));
+    }
+
+    if file.map.len() != file.height {
+        return Err(format!("map has {} rows, expected {}", file.map.len(), file.height));
+    }
+
+    let mut walls = Vec::new();
+    let mut keys = Vec::new();
+    let mut doors = Vec::new();
+    let mut start = None;
+    let mut portal_cells: Vec<Vec<Coord>> = vec![Vec::new(); MAX_PORTAL_PAIRS];
+    let mut puzzle_food: Vec<Option<Coord>> = vec![None; MAX_PUZZLE_FOOD];
+
+    for (i, line) in file.map.iter().enumerate() {
+        if line.chars().count() != file.width {
+            return Err(format!("map row {} has {} cells, expected {}", i + 1, line.chars().count(), file.width));
+        }
+
+        for (j, cell) in line.chars().enumerate() {
+            match cell {
+                WALL_CHAR => walls.push((i, j)),
+                FLOOR_CHAR => {}
+                KEY_CHAR => keys.push((i, j)),
+                DOOR_CHAR => doors.push((i, j)),
+                START_CHAR if start.is_none() => start = Some((i, j)),
+                START_CHAR => return Err(String::from("map has more than one start cell")),
+                _ if cell.is_ascii_digit() => portal_cells[cell as usize - '0' as usize].push((i, j)),
+                _ if cell.is_ascii_lowercase() => {
+                    let slot = &mut puzzle_food[cell as usize - 'a' as usize];
+
+                    if slot.is_some() { return Err(format!("puzzle food '{}' appears more than once", cell)); }
+
+                    *slot = Some((i, j));
+                }
+                _ => return Err(format!("unknown map cell '{}' at row {}, column {}", cell, i + 1, j + 1)),
+            }
+        }
+    }
+
+    let Some(start) = start else { return Err(String::from("map has no start cell")); };
+
+    if !file.food.is_empty() && file.food.iter().all(|spawn| spawn.weight == 0) {
+        return Err(String::from("food table needs at least one entry with a weight above 0"));
+    }
+
+    // Puzzle food is eaten in letter order, so the letters may not skip any.
+    let food_count = puzzle_food.iter().take_while(|food| food.is_some()).count();
+
+    if let Some(index) = puzzle_food.iter().skip(food_count).position(|food| food.is_some()) {
+        return Err(format!("puzzle food '{}' is used but '{}' is missing", get_puzzle_food_char(food_count + index), get_puzzle_food_char(food_count)));
+    }
+
+    let mut portals = Vec::new();
+    let mut portal_digits = Vec::new();
+
+    for (digit, cells) in portal_cells.iter().enumerate() {
+        match cells.len() {
+            0 => {}
+            2 => {
+                portals.push((cells[0], cells[1]));
+                portal_digits.push(digit);
+            }
+            count => return Err(format!("portal '{}' appears {} times, portals come in pairs", get_portal_char(digit), count)),
+        }
+    }
+
+    Ok(Level {
+        name: file.name.clone(),
+        author: file.author.clone(),
+        width: file.width,
+        height: file.height,
+        start,
+        start_direction: file.start_direction,
+        walls,
+        edges: file.edges,
+        portals,
+        portal_digits,
+        hazards: file.hazards.clone(),
+        food_table: file.food.clone(),
+        puzzle_food: puzzle_food.into_iter().flatten().collect(),
+        move_limit: file.moves,
+        keys,
+        doors,
+        switches: file.switches.clone(),
+        tiles: file.tiles.clone(),
+        source: None,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_map(map: &[&str], switches: &str) -> Level {
+        let content = format!(
+            r#"{{ "name": "Test", "author": "Test", "width": {}, "height": {}, "start_direction": "Right", "edges": "Solid", "map": {:?}, "switches": [{}] }}"#,
+            map[0].len(), map.len(), map, switches,
+        );
+
+        parse_level(&content).unwrap()
+    }
+
+    #[test]
+    fn built_in_levels_are_valid() {
+        for content in BUILT_IN_LEVELS {
+            let level = parse_level(content).unwrap();
+
+            assert_eq!(validate_level(&level), Ok(()), "{}", level.name);
+        }
+    }
+
+    #[test]
+    fn key_behind_its_own_door_is_rejected() {
+        let level = parse_map(&["S.....#...", "......D.K.", "......#...", "......#...", "......#..."], "");
+        let issues = validate_level(&level).unwrap_err();
+
+        assert!(issues.iter().any(|issue| issue.starts_with("key at (row 2, column 9)")), "{:?}", issues);
+    }
+
+    #[test]
+    fn chained_keys_and_doors_are_accepted() {
+        let level = parse_map(&["S....#....#...", "..K..D..K.D...", ".....#....#...", ".....#....#...", ".....#....#..."], "");
+
+        assert_eq!(validate_level(&level), Ok(()));
+    }
+
+    #[test]
+    fn switch_behind_its_own_gate_is_rejected() {
+        let level = parse_map(
+            &["S.....#...", "..........", "......#...", "......#...", "......#..."],
+            r#"{ "position": [1, 8], "gates": [[1, 6]] }"#,
+        );
+        let issues = validate_level(&level).unwrap_err();
+
+        assert!(issues.iter().any(|issue| issue.starts_with("switch at (row 2, column 9)")), "{:?}", issues);
+    }
+
+    #[test]
+    fn reachable_key_and_switch_are_accepted() {
+        let level = parse_map(
+            &["S.....#...", ".K....D...", "......#...", "......#...", "..........", "......#..."],
+            r#"{ "position": [4, 2], "gates": [[4, 6]] }"#,
+        );
+
+        assert_eq!(validate_level(&level), Ok(()));
+    }
+
+    #[test]
+    fn portal_digits_are_kept() {
+        let level = parse_map(&["S.3...", "......", "....3.", "7....7"], "");
+
+        assert_eq!(level.portal_digits, vec![3, 7]);
+        assert_eq!(level_to_file(&level).map, vec!["S.3...", "......", "....3.", "7....7"]);
+    }
+}
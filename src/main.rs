@@ -1,21 +1,63 @@
 use std::fs;
+use std::path::Path;
 use crate::utils::*;
 
 use std::time::{Duration, Instant};
 use crossterm::terminal::{enable_raw_mode, disable_raw_mode};
 use crossterm::event::{poll, read, Event};
 use serde_json::to_string_pretty;
-use crate::models::{AppState, GameState, MenusEnum, ScreensEnum, State, FIELD_SIZE, SCREEN_SIZE};
+use crate::models::{AppState, CastRecorder, EditorState, GameModesEnum, GameState, MenusEnum, ScreensEnum, State, Stats, LEVEL_STYLES, SCREEN_SIZE};
 
 mod utils;
 mod models;
 mod menu;
 mod game;
 mod leaderboard;
+mod recorder;
+mod levels;
+mod editor;
+mod generator;
+mod hazards;
+mod food;
+mod bonus;
+mod campaign;
+mod power_ups;
+mod modes;
+mod survival;
+mod zen;
+mod puzzle;
+mod daily;
+mod codes;
+mod modifiers;
+mod fog;
+mod camera;
+mod triggers;
+mod tiles;
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.len() > 2 && args[1] == "validate-level" {
+        validate_level_file(&args[2]);
+        return;
+    }
+
+    if args.len() > 2 && args[1] == "generate-level" {
+        print_generated_level(&args[2], args.get(3));
+        return;
+    }
+
+    // The recording file is opened before raw mode is on, so a bad path can be
+    // reported and the terminal is left as it was.
+    let recorder = if args.get(1).is_some_and(|command| command == "record") {
+        Some(open_recording(args.get(2)))
+    } else {
+        None
+    };
+
     let mut state = init_game_state();
     let mut last_update = Instant::now();
+    let mut last_clock_update = Instant::now();
     let mut ticks = 0;
     let update_interval = Duration::from_millis(100);
 
@@ -25,6 +67,8 @@ fn main() {
     check_file_exists("settings.json");
     set_initial_state(&mut state);
 
+    state.app_state.recorder = recorder;
+
     while state.app_state.app_running {
         ticks += 1;
 
@@ -32,18 +76,32 @@ fn main() {
         check_new_leaderboard_score(&mut state);
         check_view_offset(&mut state.app_state);
         check_for_keyboard_event(&mut state);
+        update_game_clock(&mut state, &mut last_clock_update);
         update_game_state(&mut state, &mut ticks, &mut last_update, update_interval);
         render(&mut state, &mut last_update, update_interval);
+        flush_output(&mut state);
     }
 
     toggle_cursor_visibility(true);
+    flush_output(&mut state);
     disable_raw_mode().unwrap();
+
+    if let Some(mut recorder) = state.app_state.recorder.take() {
+        let result = recorder::stop_recording(&mut recorder);
+
+        recorder::check_write(&mut state.app_state, result);
+    }
+
+    if let Some(error) = &state.app_state.recording_error {
+        eprintln!("recording stopped early: {}", error);
+    }
 }
 
 fn init_game_state() -> State {
     let difficulty = 1;
     let level = 2;
-    let game_state = GameState::new(difficulty, level);
+    let (levels, level_errors) = levels::load_levels();
+    let game_state = GameState::new(difficulty, &levels[level as usize]);
     let app_state = AppState {
         difficulty,
         level,
@@ -57,6 +115,21 @@ fn init_game_state() -> State {
         game_started: false,
         new_score: None,
         leaderboard: None,
+        stats: Stats::default(),
+        speed_up: false,
+        campaign: None,
+        mode: GameModesEnum::Classic,
+        modifiers: Vec::new(),
+        leaderboard_mode: GameModesEnum::Classic,
+        recorder: None,
+        recording_error: None,
+        levels,
+        puzzles: levels::load_puzzles(),
+        puzzle_records: Vec::new(),
+        level_errors,
+        editor: EditorState::default(),
+        code_input: String::new(),
+        code_error: None,
     };
 
     State { game_state, app_state }
@@ -74,12 +147,73 @@ fn set_initial_state(state: &mut State) {
     let saved_state: State = parsed.unwrap();
 
     state.game_state = saved_state.game_state;
-    state.app_state.leaderboard = saved_state.app_state.leaderboard;
+
+    // Games saved before the difficulty was stored with them.
+    if state.game_state.difficulty == 0 {
+        state.game_state.difficulty = saved_state.app_state.difficulty;
+    }
+
+    state.app_state.stats = saved_state.app_state.stats;
+    state.app_state.speed_up = saved_state.app_state.speed_up;
+    state.app_state.campaign = saved_state.app_state.campaign;
+    state.app_state.mode = saved_state.app_state.mode;
+    state.app_state.modifiers = saved_state.app_state.modifiers;
+    state.app_state.puzzle_records = saved_state.app_state.puzzle_records;
     state.app_state.difficulty = saved_state.app_state.difficulty;
-    state.app_state.level = saved_state.app_state.level;
+    state.app_state.level = saved_state.app_state.level.min(state.app_state.levels.len() as u16);
     state.app_state.game_started = saved_state.app_state.game_started;
 }
 
+fn open_recording(path: Option<&String>) -> CastRecorder {
+    let Some(path) = path else {
+        eprintln!("usage: snake record <file.cast>");
+        std::process::exit(1);
+    };
+
+    match recorder::start_recording(path) {
+        Ok(recorder) => recorder,
+        Err(error) => {
+            eprintln!("{}: cannot start recording: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn validate_level_file(path: &str) {
+    match levels::load_level_file(Path::new(path)) {
+        Ok(level) => println!("{}: '{}' by {} is a valid level", path, level.name, level.author),
+        Err(issues) => {
+            eprintln!("{}: level is not playable", path);
+
+            for issue in issues {
+                eprintln!("  - {}", issue);
+            }
+
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_generated_level(seed: &str, style: Option<&String>) {
+    let Ok(seed) = seed.parse::<u64>() else {
+        eprintln!("seed must be a number");
+        std::process::exit(1);
+    };
+
+    let level = match style {
+        None => generator::generate_random_level(seed),
+        Some(name) => match LEVEL_STYLES.iter().find(|s| generator::get_style_name(**s).eq_ignore_ascii_case(name)) {
+            Some(style) => generator::generate_level(seed, *style),
+            None => {
+                eprintln!("unknown style '{}', expected rooms, maze, pillars or symmetric", name);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    println!("{}", to_string_pretty(&levels::level_to_file(&level)).unwrap());
+}
+
 fn check_for_keyboard_event(state: &mut State) {
     let has_event = poll(Duration::from_millis(10)).unwrap();
 
@@ -94,6 +228,7 @@ fn check_for_keyboard_event(state: &mut State) {
         ScreensEnum::Menu => menu::process_keyboard_event(state, key_event),
         ScreensEnum::Game => game::process_keyboard_event(state, key_event),
         ScreensEnum::Leaderboard => leaderboard::process_keyboard_event(state, key_event),
+        ScreensEnum::Editor => editor::process_keyboard_event(state, key_event),
     }
 }
 
@@ -108,6 +243,7 @@ fn render(state: &mut State, last_update: &mut Instant, update_interval: Duratio
         ScreensEnum::Game => game::render(state),
         ScreensEnum::Menu => menu::render(state),
         ScreensEnum::Leaderboard => leaderboard::render(state),
+        ScreensEnum::Editor => editor::render(state),
     }
 
     *last_update = Instant::now();
@@ -115,16 +251,27 @@ fn render(state: &mut State, last_update: &mut Instant, update_interval: Duratio
 
 fn update_game_state(state: &mut State, ticks: &mut u64, last_update: &mut Instant, update_interval: Duration) {
     if last_update.elapsed() < update_interval { return; }
-    if *ticks < state.game_state.required_ticks { return; }
+    if *ticks < game::get_required_ticks(&state.game_state) { return; }
 
-    match state.app_state.selected_screen {
-        ScreensEnum::Game => game::update_game_state(state),
-        _ => {}
+    if state.app_state.selected_screen == ScreensEnum::Game {
+        game::update_game_state(state);
     }
 
     *ticks = 0;
 }
 
+// The clock only runs on the game screen, so time spent in the menu does not
+// count against the game.
+fn update_game_clock(state: &mut State, last_clock_update: &mut Instant) {
+    let elapsed = last_clock_update.elapsed();
+
+    *last_clock_update = Instant::now();
+
+    if state.app_state.selected_screen == ScreensEnum::Game {
+        game::update_game_clock(state, elapsed);
+    }
+}
+
 fn check_view_offset(game_state: &mut AppState) {
     let (offset_left, offset_top) = get_view_offset(SCREEN_SIZE.0, SCREEN_SIZE.1);
     let (curr_offset_left, curr_offset_top) = game_state.view_offset;
@@ -143,9 +290,9 @@ fn check_if_screen_changed(app_state: &mut AppState) {
     for _ in 0..SCREEN_SIZE.1 {
         set_col_position(app_state.view_offset.0);
 
-        for _ in 0..SCREEN_SIZE.0 { print!(" "); }
+        for _ in 0..SCREEN_SIZE.0 { out!(" "); }
 
-        println!();
+        outln!();
     }
 
     app_state.screen_changed = false;
@@ -166,11 +313,22 @@ fn check_file_exists(file_name: &str) {
 }
 
 fn check_new_leaderboard_score(state: &mut State) {
-    let new_score = state.app_state.new_score;
+    if let Some(entry) = state.app_state.new_score.take() {
+        leaderboard::check_if_new_record(state, entry);
+    }
+}
+
+fn flush_output(state: &mut State) {
+    let frame = take_frame();
+
+    if frame.is_empty() { return; }
+
+    flush_frame(&frame);
+
+    if let Some(recorder) = state.app_state.recorder.as_mut() {
+        let result = recorder::record_output(recorder, &frame);
 
-    if new_score.is_some() {
-        leaderboard::check_if_new_record(state, new_score.unwrap());
-        state.app_state.new_score = None;
+        recorder::check_write(&mut state.app_state, result);
     }
 }
 
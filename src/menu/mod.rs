@@ -1,12 +1,37 @@
 use crossterm::event::{KeyCode, KeyEvent};
-use crate::models::{GameState, MainMenuItemsEnum, MenuItem, MenusEnum, ScreensEnum, State, DIFFICULTY_MENU_ITEMS, LEVEL_MENU_ITEMS, MAIN_MENU_ITEMS, SCREEN_SIZE};
-use crate::utils::{print_empty_lines, set_col_position};
+use crate::campaign;
+use crate::codes;
+use crate::daily;
+use crate::editor;
+use crate::modes;
+use crate::modifiers;
+use crate::puzzle;
+use crate::levels::get_selected_level;
+use crate::models::{GameModesEnum, MainMenuItemsEnum, MenuItem, MenusEnum, ScreensEnum, State, DIFFICULTY_MENU_ITEMS, GAME_MODE_MENU_ITEMS, MAIN_MENU_ITEMS, MODIFIER_RULES, RANDOM_LEVEL_LABEL, SCREEN_SIZE, SPEED_UP_MENU_ITEMS};
+use crate::utils::{get_short_seed, get_today, outln, print_empty_lines, set_col_position, wrap_text};
+
+const MENU_ROWS: usize = 20;
 
 pub fn process_keyboard_event(state: &mut State, key_event: KeyEvent) {
+    if state.app_state.selected_menu == MenusEnum::EnterCode {
+        process_code_event(state, key_event);
+        return;
+    }
+
+    if state.app_state.selected_menu == MenusEnum::LevelErrors {
+        process_level_errors_event(state, key_event);
+        return;
+    }
+
     match key_event.code {
         KeyCode::Down => set_selected_menu(state, true),
         KeyCode::Up => set_selected_menu(state, false),
         KeyCode::Enter => process_menu_selection(state),
+        KeyCode::Char('e' | 'E') if state.app_state.selected_menu == MenusEnum::SelectLevel && !state.app_state.level_errors.is_empty() => {
+            state.app_state.selected_menu = MenusEnum::LevelErrors;
+            state.app_state.selected_menu_item = 0;
+            state.app_state.screen_changed = true;
+        }
         KeyCode::Esc => {
             state.app_state.selected_menu = MenusEnum::MainMenu;
             state.app_state.selected_menu_item = 0;
@@ -16,37 +41,164 @@ pub fn process_keyboard_event(state: &mut State, key_event: KeyEvent) {
 }
 
 pub fn render(state: &mut State) {
-    let menu_labels: Vec<&str> = match state.app_state.selected_menu {
-        MenusEnum::MainMenu => get_main_menu_items(state).iter().map(|x| x.label).collect(),
-        MenusEnum::SelectDifficulty => DIFFICULTY_MENU_ITEMS.iter().map(|x| x.label).collect(),
-        MenusEnum::SelectLevel => LEVEL_MENU_ITEMS.iter().map(|x| x.label).collect(),
+    if state.app_state.selected_menu == MenusEnum::EnterCode {
+        render_code_input(state);
+        return;
+    }
+
+    if state.app_state.selected_menu == MenusEnum::LevelErrors {
+        render_level_errors(state);
+        return;
+    }
+
+    let menu_labels: Vec<String> = match state.app_state.selected_menu {
+        MenusEnum::MainMenu => get_main_menu_items(state).iter().map(|x| x.label.to_string()).collect(),
+        MenusEnum::SelectMode => GAME_MODE_MENU_ITEMS.iter().map(|x| x.label.to_string()).collect(),
+        MenusEnum::SelectModifiers => MODIFIER_RULES.iter().map(|x| modifiers::get_menu_label(&state.app_state.modifiers, x)).collect(),
+        MenusEnum::SelectDifficulty => DIFFICULTY_MENU_ITEMS.iter().map(|x| x.label.to_string()).collect(),
+        MenusEnum::SelectSpeedUp => SPEED_UP_MENU_ITEMS.iter().map(|x| x.label.to_string()).collect(),
+        MenusEnum::SelectLevel => state.app_state.levels
+            .iter()
+            .map(|x| x.name.clone())
+            .chain([RANDOM_LEVEL_LABEL.to_string()])
+            .collect(),
+        MenusEnum::SelectPuzzle => state.app_state.puzzles
+            .iter()
+            .map(|x| puzzle::get_menu_label(&state.app_state, x))
+            .collect(),
+        MenusEnum::EnterCode | MenusEnum::LevelErrors => Vec::new(),
     };
 
-    let app_state = &mut state.app_state;
+    let app_state = &state.app_state;
+
+    let rows_per_item = if menu_labels.len() * 2 > MENU_ROWS { 1 } else { 2 };
+    let visible_items = MENU_ROWS / rows_per_item;
+    let first_item = (app_state.selected_menu_item + 1).saturating_sub(visible_items);
 
     print_empty_lines(5);
 
-    for i in 0..menu_labels.len() {
-        let option_offset = (SCREEN_SIZE.0 - (menu_labels[i].len() as u16 + 5)) / 2;
+    for (i, label) in menu_labels.iter().enumerate().skip(first_item).take(visible_items) {
+        let option_offset = SCREEN_SIZE.0.saturating_sub(label.len() as u16 + 5) / 2;
         let is_selected = app_state.selected_menu_item == i;
 
         set_col_position(app_state.view_offset.0 + option_offset);
 
         if is_selected {
-            println!(">> {} <<", menu_labels[i]);
+            outln!(">> {} <<", label);
         } else {
-            println!("   {}   ", menu_labels[i]);
+            outln!("   {}   ", label);
         }
 
-        println!()
+        if rows_per_item == 2 { outln!(); }
+    }
+
+    if app_state.selected_menu == MenusEnum::SelectLevel && !app_state.level_errors.is_empty() {
+        let messages = [
+            format!("{} level file(s) skipped", app_state.level_errors.len()),
+            String::from("'E' to see why"),
+        ];
+
+        for message in messages {
+            set_col_position(app_state.view_offset.0 + SCREEN_SIZE.0.saturating_sub(message.len() as u16) / 2);
+            outln!("{}", message);
+        }
+    }
+}
+
+// Lists why each skipped level file was rejected; the arrow keys scroll when
+// the reasons do not fit on the screen.
+fn process_level_errors_event(state: &mut State, key_event: KeyEvent) {
+    let app_state = &mut state.app_state;
+    let last_line = get_level_error_lines(&app_state.level_errors).len().saturating_sub(MENU_ROWS);
+
+    match key_event.code {
+        KeyCode::Down => app_state.selected_menu_item = (app_state.selected_menu_item + 1).min(last_line),
+        KeyCode::Up => app_state.selected_menu_item = app_state.selected_menu_item.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Esc => {
+            app_state.selected_menu = MenusEnum::SelectLevel;
+            app_state.selected_menu_item = app_state.level as usize;
+        }
+        _ => {}
+    }
+
+    app_state.screen_changed = true;
+}
+
+fn render_level_errors(state: &mut State) {
+    let app_state = &state.app_state;
+    let lines = get_level_error_lines(&app_state.level_errors);
+
+    print_empty_lines(3);
+
+    for line in ["Skipped level files:", "'Esc' to go back"] {
+        set_col_position(app_state.view_offset.0 + SCREEN_SIZE.0.saturating_sub(line.len() as u16) / 2);
+        outln!("{}", line);
+    }
+
+    outln!();
+
+    for line in lines.iter().skip(app_state.selected_menu_item).take(MENU_ROWS) {
+        set_col_position(app_state.view_offset.0);
+        outln!("{}", line);
+    }
+}
+
+fn get_level_error_lines(errors: &[String]) -> Vec<String> {
+    errors
+        .iter()
+        .flat_map(|error| wrap_text(error, SCREEN_SIZE.0 as usize).into_iter().chain([String::new()]))
+        .collect()
+}
+
+fn process_code_event(state: &mut State, key_event: KeyEvent) {
+    let app_state = &mut state.app_state;
+
+    match key_event.code {
+        KeyCode::Char(c) => codes::add_code_char(&mut app_state.code_input, c),
+        KeyCode::Backspace => { app_state.code_input.pop(); }
+        KeyCode::Enter => codes::play_code(state),
+        KeyCode::Esc => {
+            app_state.selected_menu = MenusEnum::MainMenu;
+            state.app_state.selected_menu_item = get_main_menu_item_index(state, MainMenuItemsEnum::PlayCode);
+        }
+        _ => {}
+    }
+
+    state.app_state.screen_changed = true;
+}
+
+fn render_code_input(state: &mut State) {
+    let app_state = &state.app_state;
+    let lines = [
+        String::from("Paste a challenge code:"),
+        String::from(""),
+        format!("{}_", app_state.code_input),
+        String::from(""),
+        app_state.code_error.clone().unwrap_or_default(),
+        String::from(""),
+        String::from("'Enter' to play"),
+        String::from("'Esc' to go back"),
+    ];
+
+    print_empty_lines(5);
+
+    for line in lines {
+        set_col_position(app_state.view_offset.0 + SCREEN_SIZE.0.saturating_sub(line.chars().count() as u16) / 2);
+        outln!("{}", line);
     }
 }
 
 fn process_menu_selection(state: &mut State) {
     match state.app_state.selected_menu {
         MenusEnum::MainMenu => process_main_menu_selection(state),
+        MenusEnum::SelectMode => process_mode_menu_selection(state),
+        MenusEnum::SelectModifiers => process_modifiers_menu_selection(state),
         MenusEnum::SelectDifficulty => process_difficulty_menu_selection(state),
-        MenusEnum::SelectLevel => process_level_menu_selection(state)
+        MenusEnum::SelectSpeedUp => process_speed_up_menu_selection(state),
+        MenusEnum::SelectLevel => process_level_menu_selection(state),
+        MenusEnum::SelectPuzzle => process_puzzle_menu_selection(state),
+        MenusEnum::EnterCode => codes::play_code(state),
+        MenusEnum::LevelErrors => {}
     }
 
     state.app_state.screen_changed = true;
@@ -68,12 +220,42 @@ fn process_main_menu_selection(state: &mut State) {
             app_state.selected_screen = ScreensEnum::Game;
             app_state.game_started = true;
             app_state.selected_menu_item = 0;
-            state.game_state = GameState::new(app_state.difficulty, app_state.level);
+            state.game_state = modes::new_game(app_state.difficulty, app_state.mode, &get_selected_level(app_state), Some(get_short_seed()));
+            state.game_state.modifiers = app_state.modifiers.clone();
+        }
+        MainMenuItemsEnum::Campaign => {
+            app_state.selected_menu_item = 0;
+            campaign::start_campaign(state);
+        }
+        MainMenuItemsEnum::Puzzles => {
+            app_state.selected_menu = MenusEnum::SelectPuzzle;
+            app_state.selected_menu_item = 0;
+        }
+        MainMenuItemsEnum::DailyChallenge => {
+            app_state.selected_menu_item = 0;
+            daily::start_daily(state);
+        }
+        MainMenuItemsEnum::PlayCode => {
+            app_state.selected_menu = MenusEnum::EnterCode;
+            app_state.selected_menu_item = 0;
+            app_state.code_input.clear();
+            app_state.code_error = None;
         }
         MainMenuItemsEnum::Leaderboard => {
             app_state.selected_menu_item = 0;
             app_state.selected_screen = ScreensEnum::Leaderboard;
         }
+        MainMenuItemsEnum::GameMode => {
+            app_state.selected_menu = MenusEnum::SelectMode;
+            app_state.selected_menu_item = GAME_MODE_MENU_ITEMS
+                .iter()
+                .position(|x| x.value == app_state.mode)
+                .unwrap()
+        }
+        MainMenuItemsEnum::Modifiers => {
+            app_state.selected_menu = MenusEnum::SelectModifiers;
+            app_state.selected_menu_item = 0;
+        }
         MainMenuItemsEnum::Difficulty => {
             app_state.selected_menu = MenusEnum::SelectDifficulty;
             app_state.selected_menu_item = DIFFICULTY_MENU_ITEMS
@@ -81,12 +263,20 @@ fn process_main_menu_selection(state: &mut State) {
                 .position(|x| x.value == app_state.difficulty)
                 .unwrap()
         }
+        MainMenuItemsEnum::SpeedUp => {
+            app_state.selected_menu = MenusEnum::SelectSpeedUp;
+            app_state.selected_menu_item = SPEED_UP_MENU_ITEMS
+                .iter()
+                .position(|x| x.value == app_state.speed_up)
+                .unwrap()
+        }
         MainMenuItemsEnum::LevelSelection => {
             app_state.selected_menu = MenusEnum::SelectLevel;
-            app_state.selected_menu_item = LEVEL_MENU_ITEMS
-                .iter()
-                .position(|x| x.value == app_state.level)
-                .unwrap();
+            app_state.selected_menu_item = app_state.level as usize;
+        }
+        MainMenuItemsEnum::LevelEditor => {
+            app_state.selected_menu_item = 0;
+            editor::open_editor(state);
         }
         MainMenuItemsEnum::Exit => {
             app_state.app_running = false;
@@ -95,6 +285,25 @@ fn process_main_menu_selection(state: &mut State) {
     }
 }
 
+fn process_mode_menu_selection(state: &mut State) {
+    let menu_index = state.app_state.selected_menu_item;
+
+    state.app_state.game_started = false;
+    state.app_state.mode = GAME_MODE_MENU_ITEMS[menu_index].value;
+    state.app_state.selected_menu = MenusEnum::MainMenu;
+    state.app_state.selected_menu_item = get_main_menu_item_index(state, MainMenuItemsEnum::GameMode);
+    state.app_state.dirty = true;
+}
+
+// Toggles the selected modifier and stays in the menu, Esc goes back. Modifiers
+// apply from the next new game on.
+fn process_modifiers_menu_selection(state: &mut State) {
+    let rule = &MODIFIER_RULES[state.app_state.selected_menu_item];
+
+    modifiers::toggle_modifier(&mut state.app_state.modifiers, rule.kind);
+    state.app_state.dirty = true;
+}
+
 fn process_difficulty_menu_selection(state: &mut State) {
     let menu_index = state.app_state.selected_menu_item;
     let difficulty = DIFFICULTY_MENU_ITEMS[menu_index].value;
@@ -106,9 +315,19 @@ fn process_difficulty_menu_selection(state: &mut State) {
     state.app_state.dirty = true;
 }
 
+fn process_speed_up_menu_selection(state: &mut State) {
+    let menu_index = state.app_state.selected_menu_item;
+
+    state.app_state.game_started = false;
+    state.app_state.speed_up = SPEED_UP_MENU_ITEMS[menu_index].value;
+    state.app_state.selected_menu = MenusEnum::MainMenu;
+    state.app_state.selected_menu_item = get_main_menu_item_index(state, MainMenuItemsEnum::SpeedUp);
+    state.app_state.dirty = true;
+}
+
 fn process_level_menu_selection(state: &mut State) {
     let menu_index = state.app_state.selected_menu_item;
-    let level = LEVEL_MENU_ITEMS[menu_index].value;
+    let level = menu_index as u16;
 
     state.app_state.game_started = false;
     state.app_state.level = level;
@@ -117,15 +336,33 @@ fn process_level_menu_selection(state: &mut State) {
     state.app_state.dirty = true;
 }
 
+fn process_puzzle_menu_selection(state: &mut State) {
+    let app_state = &mut state.app_state;
+    let level = &app_state.puzzles[app_state.selected_menu_item];
+
+    state.game_state = modes::new_game(app_state.difficulty, GameModesEnum::Puzzle, level, None);
+    app_state.game_started = true;
+    app_state.selected_menu = MenusEnum::MainMenu;
+    app_state.selected_menu_item = 0;
+    app_state.selected_screen = ScreensEnum::Game;
+}
+
 fn set_selected_menu(state: &mut State, is_increment: bool) {
     let max_items = match state.app_state.selected_menu {
         MenusEnum::MainMenu => get_main_menu_items(state).len(),
+        MenusEnum::SelectMode => GAME_MODE_MENU_ITEMS.len(),
+        MenusEnum::SelectModifiers => MODIFIER_RULES.len(),
         MenusEnum::SelectDifficulty => DIFFICULTY_MENU_ITEMS.len(),
-        MenusEnum::SelectLevel => LEVEL_MENU_ITEMS.len(),
+        MenusEnum::SelectSpeedUp => SPEED_UP_MENU_ITEMS.len(),
+        MenusEnum::SelectLevel => state.app_state.levels.len() + 1,
+        MenusEnum::SelectPuzzle => state.app_state.puzzles.len(),
+        MenusEnum::EnterCode | MenusEnum::LevelErrors => 1,
     };
 
     let app_state = &mut state.app_state;
     let selected = app_state.selected_menu_item;
+
+    app_state.screen_changed = true;
     let is_first = selected == 0;
     let is_last = selected == max_items - 1;
 
@@ -138,11 +375,16 @@ fn set_selected_menu(state: &mut State, is_increment: bool) {
 
 fn get_main_menu_items(state: &State) -> Vec<MenuItem<MainMenuItemsEnum>> {
     let game_started = state.app_state.game_started;
+    let daily_in_progress = daily::is_daily_in_progress(state, get_today());
 
     MAIN_MENU_ITEMS
         .iter()
         .filter(|item| game_started || item.value != MainMenuItemsEnum::Continue)
         .cloned()
+        .map(|item| match item.value {
+            MainMenuItemsEnum::DailyChallenge if daily_in_progress => MenuItem { label: "Continue Daily", ..item },
+            _ => item,
+        })
         .collect()
 }
 
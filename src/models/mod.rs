@@ -1,21 +1,194 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::Instant;
 use serde::{Deserialize, Serialize};
 
+// Board cells shown at once; larger levels scroll, up to `MAX_WORLD_SIZE`.
 pub const FIELD_SIZE: usize = 15;
+pub const MAX_WORLD_SIZE: usize = 60;
+pub const CAMERA_MARGIN: usize = 4;
+pub const MINIMAP_SIZE: usize = 8;
 pub const SCREEN_SIZE: (u16, u16) = (30, 30);
 pub const SNAKE: u16 = 1;
 pub const FOOD: u16 = 2;
 pub const BONUS: u16 = 3;
 pub const WALL: u16 = 4;
+pub const PORTAL: u16 = 5;
+pub const HAZARD: u16 = 6;
+pub const POWER_UP: u16 = 7;
+pub const POWER_UP_SPAWN_CHANCE: usize = 30;
+pub const POWER_UP_LIFETIME: u64 = 40;
+pub const SHRINK_AMOUNT: usize = 3;
+pub const MAX_EXTRA_FOOD: usize = 2;
+pub const VICTORY_BONUS: u64 = 1000;
+pub const CAMPAIGN_LIVES: u64 = 3;
+pub const TIME_ATTACK_LIMIT_MS: u64 = 120_000;
+pub const TIME_ATTACK_FOOD_BONUS_MS: u64 = 5_000;
+pub const SURVIVAL_WALL_EVERY: u64 = 25;
+pub const SURVIVAL_WALL_POINTS: u64 = 5;
+pub const ZEN_PENALTY: u64 = 10;
+pub const ZEN_MAX_TICKS: u64 = 30;
+pub const PUZZLE_FOOD_POINTS: u64 = 10;
+pub const MOVING_FOOD_EVERY: u64 = 15;
+pub const VISION_RADIUS: usize = 3;
+pub const FOG_RADIUS: usize = 5;
+pub const SPEED_PAD_STEPS: u64 = 12;
+pub const DAILY_DIFFICULTY: u16 = 2;
+pub const LEVELS_DIR: &str = "levels";
+pub const RANDOM_LEVEL_LABEL: &str = "Random Level";
 
-pub static MAIN_MENU_ITEMS: [MenuItem<MainMenuItemsEnum>; 6] = [
+pub static MAIN_MENU_ITEMS: [MenuItem<MainMenuItemsEnum>; 14] = [
     MenuItem { label: "Continue", value: MainMenuItemsEnum::Continue },
     MenuItem { label: "New Game", value: MainMenuItemsEnum::NewGame },
+    MenuItem { label: "Campaign", value: MainMenuItemsEnum::Campaign },
+    MenuItem { label: "Puzzles", value: MainMenuItemsEnum::Puzzles },
+    MenuItem { label: "Daily Challenge", value: MainMenuItemsEnum::DailyChallenge },
+    MenuItem { label: "Play Code", value: MainMenuItemsEnum::PlayCode },
     MenuItem { label: "Leaderboard", value: MainMenuItemsEnum::Leaderboard },
+    MenuItem { label: "Game Mode", value: MainMenuItemsEnum::GameMode },
+    MenuItem { label: "Modifiers", value: MainMenuItemsEnum::Modifiers },
     MenuItem { label: "Difficulty", value: MainMenuItemsEnum::Difficulty },
+    MenuItem { label: "Speed Up", value: MainMenuItemsEnum::SpeedUp },
     MenuItem { label: "Select Level", value: MainMenuItemsEnum::LevelSelection },
+    MenuItem { label: "Level Editor", value: MainMenuItemsEnum::LevelEditor },
     MenuItem { label: "Exit", value: MainMenuItemsEnum::Exit },
 ];
 
+pub static LEVEL_STYLES: [LevelStylesEnum; 4] = [
+    LevelStylesEnum::Rooms,
+    LevelStylesEnum::Maze,
+    LevelStylesEnum::Pillars,
+    LevelStylesEnum::Symmetric,
+];
+
+pub static POWER_UP_RULES: [PowerUpRule; 5] = [
+    PowerUpRule { kind: PowerUpsEnum::SlowMotion, label: "Slow", symbol: "zz", weight: 3, min_food: 3, duration: 30 },
+    PowerUpRule { kind: PowerUpsEnum::Ghost, label: "Ghost", symbol: "gh", weight: 2, min_food: 5, duration: 20 },
+    PowerUpRule { kind: PowerUpsEnum::Shrink, label: "Shrink", symbol: "--", weight: 2, min_food: 8, duration: 0 },
+    PowerUpRule { kind: PowerUpsEnum::Multiplier, label: "x2", symbol: "x2", weight: 2, min_food: 3, duration: 40 },
+    PowerUpRule { kind: PowerUpsEnum::Magnet, label: "Magnet", symbol: "mg", weight: 1, min_food: 10, duration: 30 },
+];
+
+// Growth is how many cells the snake gains (or loses, when negative) and
+// `value` multiplies the difficulty's points per food.
+pub static FOOD_RULES: [FoodRule; 4] = [
+    FoodRule { kind: FoodKindsEnum::Normal, symbol: "■ ", value: 1, growth: 1, lifetime: None },
+    FoodRule { kind: FoodKindsEnum::Golden, symbol: "$ ", value: 5, growth: 2, lifetime: Some(30) },
+    FoodRule { kind: FoodKindsEnum::Poison, symbol: "! ", value: 0, growth: -2, lifetime: Some(60) },
+    FoodRule { kind: FoodKindsEnum::Rotten, symbol: "% ", value: 2, growth: 1, lifetime: Some(15) },
+];
+
+// Rolled every time normal food is eaten; a Normal roll means no extra food.
+pub static DEFAULT_FOOD_TABLE: [FoodSpawn; 4] = [
+    FoodSpawn { kind: FoodKindsEnum::Normal, weight: 6 },
+    FoodSpawn { kind: FoodKindsEnum::Golden, weight: 2 },
+    FoodSpawn { kind: FoodKindsEnum::Poison, weight: 2 },
+    FoodSpawn { kind: FoodKindsEnum::Rotten, weight: 3 },
+];
+
+// Used instead of the default table in these modes when the level has no table
+// of its own: Time Attack favours quick points, Survival makes room with
+// poison and Zen leaves poison out.
+pub static MODE_FOOD_TABLES: [ModeFoodTable; 3] = [
+    ModeFoodTable {
+        mode: GameModesEnum::TimeAttack,
+        table: &[
+            FoodSpawn { kind: FoodKindsEnum::Normal, weight: 5 },
+            FoodSpawn { kind: FoodKindsEnum::Golden, weight: 3 },
+            FoodSpawn { kind: FoodKindsEnum::Poison, weight: 1 },
+            FoodSpawn { kind: FoodKindsEnum::Rotten, weight: 4 },
+        ],
+    },
+    ModeFoodTable {
+        mode: GameModesEnum::Survival,
+        table: &[
+            FoodSpawn { kind: FoodKindsEnum::Normal, weight: 6 },
+            FoodSpawn { kind: FoodKindsEnum::Golden, weight: 1 },
+            FoodSpawn { kind: FoodKindsEnum::Poison, weight: 4 },
+            FoodSpawn { kind: FoodKindsEnum::Rotten, weight: 2 },
+        ],
+    },
+    ModeFoodTable {
+        mode: GameModesEnum::Zen,
+        table: &[
+            FoodSpawn { kind: FoodKindsEnum::Normal, weight: 6 },
+            FoodSpawn { kind: FoodKindsEnum::Golden, weight: 2 },
+            FoodSpawn { kind: FoodKindsEnum::Rotten, weight: 2 },
+        ],
+    },
+];
+
+// Ordered from the largest shape to the smallest: when a shape does not fit
+// anywhere on the board the bonus falls back to the next one.
+pub static BONUS_SHAPES: [BonusShape; 4] = [
+    BonusShape { cells: &[(0, 0), (0, 1), (1, 0), (1, 1)], value_percent: 100 },
+    BonusShape { cells: &[(0, 0), (1, 0), (1, 1)], value_percent: 80 },
+    BonusShape { cells: &[(0, 0), (0, 1)], value_percent: 60 },
+    BonusShape { cells: &[(0, 0)], value_percent: 40 },
+];
+
+// `trigger` is how much food brings up the bonus, `value` what it is worth when
+// it appears and `decay` how much of that it loses on every step.
+pub static BONUS_RULES: [BonusRule; 4] = [
+    BonusRule { difficulty: 1, trigger: 5, value: 100, decay: 2 },
+    BonusRule { difficulty: 2, trigger: 5, value: 100, decay: 3 },
+    BonusRule { difficulty: 3, trigger: 4, value: 120, decay: 4 },
+    BonusRule { difficulty: 4, trigger: 3, value: 150, decay: 6 },
+];
+
+// `tag` marks the modifier in leaderboard entries and challenge codes, and
+// `score_percent` scales the points from food and bonuses while it is on.
+pub static MODIFIER_RULES: [ModifierRule; 6] = [
+    ModifierRule { kind: ModifiersEnum::InvisibleBody, label: "Invisible body", tag: 'I', score_percent: 150 },
+    ModifierRule { kind: ModifiersEnum::MirroredControls, label: "Mirrored controls", tag: 'M', score_percent: 130 },
+    ModifierRule { kind: ModifiersEnum::NoBonus, label: "No bonus", tag: 'B', score_percent: 110 },
+    ModifierRule { kind: ModifiersEnum::DoubleSpeed, label: "Double speed", tag: 'D', score_percent: 150 },
+    ModifierRule { kind: ModifiersEnum::MovingFood, label: "Moving food", tag: 'F', score_percent: 120 },
+    ModifierRule { kind: ModifiersEnum::LimitedVision, label: "Limited vision", tag: 'V', score_percent: 140 },
+];
+
+// Played in order; `level` indexes the built-in levels and `food_target` is how
+// much food has to be eaten to move on to the next stage.
+pub static CAMPAIGN_STAGES: [CampaignStage; 5] = [
+    CampaignStage { level: 0, food_target: 8 },
+    CampaignStage { level: 1, food_target: 10 },
+    CampaignStage { level: 2, food_target: 12 },
+    CampaignStage { level: 3, food_target: 14 },
+    CampaignStage { level: 4, food_target: 16 },
+];
+
+pub static GAME_MODE_MENU_ITEMS: [MenuItem<GameModesEnum>; 5] = [
+    MenuItem { label: "Classic", value: GameModesEnum::Classic },
+    MenuItem { label: "Time Attack", value: GameModesEnum::TimeAttack },
+    MenuItem { label: "Survival", value: GameModesEnum::Survival },
+    MenuItem { label: "Fog of War", value: GameModesEnum::Fog },
+    MenuItem { label: "Zen", value: GameModesEnum::Zen },
+];
+
+// Each ranked mode keeps its own top 10, shown in this order on the leaderboard.
+pub static LEADERBOARD_CATEGORIES: [MenuItem<GameModesEnum>; 5] = [
+    MenuItem { label: "Classic", value: GameModesEnum::Classic },
+    MenuItem { label: "Time Attack", value: GameModesEnum::TimeAttack },
+    MenuItem { label: "Survival", value: GameModesEnum::Survival },
+    MenuItem { label: "Fog of War", value: GameModesEnum::Fog },
+    MenuItem { label: "Daily", value: GameModesEnum::Daily },
+];
+
+pub static SPEED_UP_MENU_ITEMS: [MenuItem<bool>; 2] = [
+    MenuItem { label: "Off", value: false },
+    MenuItem { label: "On", value: true },
+];
+
+// `ticks` is the starting step interval; with speed up on it drops by one tick
+// for every `every` food eaten, down to `floor`.
+pub static SPEED_CURVES: [SpeedCurve; 4] = [
+    SpeedCurve { difficulty: 1, ticks: 20, every: 2, floor: 8 },
+    SpeedCurve { difficulty: 2, ticks: 15, every: 2, floor: 6 },
+    SpeedCurve { difficulty: 3, ticks: 10, every: 3, floor: 4 },
+    SpeedCurve { difficulty: 4, ticks: 2, every: 10, floor: 1 },
+];
+
 pub static DIFFICULTY_MENU_ITEMS: [MenuItem<u16>; 4] = [
     MenuItem { label: "Easy", value: 1 },
     MenuItem { label: "Medium", value: 2 },
@@ -23,14 +196,6 @@ pub static DIFFICULTY_MENU_ITEMS: [MenuItem<u16>; 4] = [
     MenuItem { label: "Extreme", value: 4 }
 ];
 
-pub static LEVEL_MENU_ITEMS: [MenuItem<u16>; 5] = [
-    MenuItem { label: "Plain Field", value: 0 },
-    MenuItem { label: "Box", value: 1 },
-    MenuItem { label: "Labyrinth", value: 2 },
-    MenuItem { label: "Two Sides", value: 3 },
-    MenuItem { label: "Roundabout", value: 4 }
-];
-
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
 pub enum DirectionsEnum {
     Up,
@@ -39,11 +204,80 @@ pub enum DirectionsEnum {
     Right,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize, Serialize)]
+pub enum EdgesEnum {
+    #[default]
+    Wrap,
+    Solid,
+    WrapHorizontal,
+    WrapVertical,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize, Serialize)]
+pub enum GameModesEnum {
+    #[default]
+    Classic,
+    TimeAttack,
+    Survival,
+    Zen,
+    Puzzle,
+    Daily,
+    Fog,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+pub enum LevelStylesEnum {
+    Rooms,
+    Maze,
+    Pillars,
+    Symmetric,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+pub enum ModifiersEnum {
+    InvisibleBody,
+    MirroredControls,
+    NoBonus,
+    DoubleSpeed,
+    MovingFood,
+    LimitedVision,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+pub enum PowerUpsEnum {
+    SlowMotion,
+    Ghost,
+    Shrink,
+    Multiplier,
+    Magnet,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+pub enum FoodKindsEnum {
+    Normal,
+    Golden,
+    Poison,
+    Rotten,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum VisibilityEnum {
+    Visible,
+    Remembered,
+    Hidden,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
 pub enum MenusEnum {
     MainMenu,
+    SelectMode,
+    SelectModifiers,
     SelectDifficulty,
+    SelectSpeedUp,
     SelectLevel,
+    SelectPuzzle,
+    EnterCode,
+    LevelErrors,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
@@ -51,15 +285,24 @@ pub enum ScreensEnum {
     Game,
     Menu,
     Leaderboard,
+    Editor,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
 pub enum MainMenuItemsEnum {
     Continue,
     NewGame,
+    Campaign,
+    Puzzles,
+    DailyChallenge,
+    PlayCode,
     Leaderboard,
+    GameMode,
+    Modifiers,
     Difficulty,
+    SpeedUp,
     LevelSelection,
+    LevelEditor,
     Exit
 }
 
@@ -71,6 +314,68 @@ pub struct MenuItem<T> {
 
 pub type Coord = (usize, usize);
 
+// `min_food` is how much food has to be eaten before the power-up can show up,
+// `duration` is how many steps its effect lasts (0 for instant effects).
+pub struct PowerUpRule {
+    pub kind: PowerUpsEnum,
+    pub label: &'static str,
+    pub symbol: &'static str,
+    pub weight: usize,
+    pub min_food: u64,
+    pub duration: u64,
+}
+
+pub struct ModifierRule {
+    pub kind: ModifiersEnum,
+    pub label: &'static str,
+    pub tag: char,
+    pub score_percent: u64,
+}
+
+pub struct FoodRule {
+    pub kind: FoodKindsEnum,
+    pub symbol: &'static str,
+    pub value: u64,
+    pub growth: i64,
+    pub lifetime: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct FoodSpawn {
+    #[serde(rename = "type")]
+    pub kind: FoodKindsEnum,
+    pub weight: usize,
+}
+
+pub struct BonusShape {
+    pub cells: &'static [Coord],
+    pub value_percent: u64,
+}
+
+pub struct SpeedCurve {
+    pub difficulty: u16,
+    pub ticks: u64,
+    pub every: u64,
+    pub floor: u64,
+}
+
+pub struct CampaignStage {
+    pub level: usize,
+    pub food_target: u64,
+}
+
+pub struct ModeFoodTable {
+    pub mode: GameModesEnum,
+    pub table: &'static [FoodSpawn],
+}
+
+pub struct BonusRule {
+    pub difficulty: u16,
+    pub trigger: u64,
+    pub value: u64,
+    pub decay: u64,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct State {
     pub game_state: GameState,
@@ -88,73 +393,322 @@ pub struct AppState {
     pub difficulty: u16,
     pub level: u16,
     pub game_started: bool,
-    pub new_score: Option<u64>,
-    pub leaderboard: Option<Vec<u64>>,
+    #[serde(skip)]
+    pub new_score: Option<LeaderboardEntry>,
+    #[serde(skip)]
+    pub leaderboard: Option<Vec<LeaderboardEntry>>,
     pub dirty: bool,
+    #[serde(default)]
+    pub stats: Stats,
+    #[serde(default)]
+    pub speed_up: bool,
+    #[serde(default)]
+    pub campaign: Option<Campaign>,
+    #[serde(default)]
+    pub mode: GameModesEnum,
+    #[serde(default)]
+    pub modifiers: Vec<ModifiersEnum>,
+    #[serde(skip)]
+    pub leaderboard_mode: GameModesEnum,
+    #[serde(default)]
+    pub puzzle_records: Vec<PuzzleRecord>,
+    #[serde(skip)]
+    pub recorder: Option<CastRecorder>,
+    #[serde(skip)]
+    pub recording_error: Option<String>,
+    #[serde(skip)]
+    pub levels: Vec<Level>,
+    #[serde(skip)]
+    pub puzzles: Vec<Level>,
+    #[serde(skip)]
+    pub level_errors: Vec<String>,
+    #[serde(skip)]
+    pub editor: EditorState,
+    #[serde(skip)]
+    pub code_input: String,
+    #[serde(skip)]
+    pub code_error: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LeaderboardEntry {
+    pub score: u64,
+    pub victory: bool,
+    pub mode: GameModesEnum,
+    pub day: Option<u64>,
+    pub modifiers: Vec<ModifiersEnum>,
+}
+
+// `last_daily` is the day (since 1970-01-01) of the last ranked daily challenge.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    pub games_played: u64,
+    pub victories: u64,
+    #[serde(default)]
+    pub daily_streak: u64,
+    #[serde(default)]
+    pub last_daily: Option<u64>,
+}
+
+// Only the first daily challenge of the day is ranked, replays are practice.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Daily {
+    pub day: u64,
+    pub ranked: bool,
+}
+
+// `score` is the score the stage was started with, so a lost life restarts the
+// stage from there.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Campaign {
+    pub stage: usize,
+    pub lives: u64,
+    pub score: u64,
+}
+
+// Puzzles are told apart by name, `moves` is the fewest moves it was solved in.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct PuzzleRecord {
+    pub name: String,
+    pub moves: u64,
+}
+
+// `next` indexes the level's puzzle food that has to be eaten next.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub next: usize,
+    pub moves: u64,
+}
+
+// Everything needed to play exactly the same game again, see `codes::encode`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChallengeCode {
+    pub mode: GameModesEnum,
+    pub difficulty: u16,
+    pub level: LevelSource,
+    pub seed: u64,
+    pub modifiers: Vec<ModifiersEnum>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum LevelSource {
+    BuiltIn(usize),
+    Generated { seed: u64, style: LevelStylesEnum },
+}
+
+#[derive(Default)]
+pub struct EditorState {
+    pub level: Level,
+    pub cursor: Coord,
+    pub naming: bool,
+    pub messages: Vec<String>,
+    pub pending_portal: Option<Coord>,
+    pub play_testing: bool,
+    pub suspended_game: Option<(GameState, bool)>,
+    // The file this copy was last saved to and the name it was saved under;
+    // saving again overwrites that file and nothing else.
+    pub saved_as: Option<(PathBuf, String)>,
+}
+
+pub struct CastRecorder {
+    pub writer: BufWriter<File>,
+    pub started_at: Instant,
+    pub terminal_size: (u16, u16),
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GameState {
+    #[serde(default)]
+    pub difficulty: u16,
     pub grid: Vec<Vec<u16>>,
     pub snake_direction: DirectionsEnum,
     pub next_direction: DirectionsEnum,
     pub snake_body: Vec<Coord>,
-    pub food_position: Coord,
-    pub bonus_position: Option<Coord>,
+    #[serde(default)]
+    pub food: Vec<Food>,
+    #[serde(default)]
+    pub bonus_cells: Vec<Coord>,
     pub bonus_value: u64,
     pub food_eaten: u64,
-    pub ate_food: bool,
+    #[serde(default)]
+    pub pending_growth: u64,
     pub game_over: bool,
+    #[serde(default)]
+    pub victory: bool,
     pub score: u64,
     pub required_ticks: u64,
     pub food_for_bonus_needed: u64,
     pub freeze: bool,
+    #[serde(default)]
+    pub level: Level,
+    #[serde(default)]
+    pub hazards: Vec<Hazard>,
+    #[serde(default)]
+    pub steps: u64,
+    #[serde(default)]
+    pub power_up: Option<PowerUp>,
+    #[serde(default)]
+    pub effects: Vec<ActiveEffect>,
+    #[serde(default)]
+    pub campaign: Option<Campaign>,
+    #[serde(default)]
+    pub mode: GameModesEnum,
+    // Milliseconds actually played, not counting time spent in the menu or
+    // waiting for a key after "Continue".
+    #[serde(default)]
+    pub play_time: u64,
+    #[serde(default)]
+    pub time_limit: Option<u64>,
+    #[serde(default)]
+    pub puzzle: Option<Puzzle>,
+    #[serde(default)]
+    pub daily: Option<Daily>,
+    // `seed` starts a game over the same way, `rng` is the generator's current
+    // state. Both are empty for ordinary, truly random games.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub rng: Option<u64>,
+    #[serde(default)]
+    pub from_code: bool,
+    #[serde(default)]
+    pub modifiers: Vec<ModifiersEnum>,
+    // Walls a fog of war game has already seen; they stay drawn once out of sight.
+    #[serde(default)]
+    pub remembered_walls: Vec<Coord>,
+    // Top left cell of the part of the board on screen.
+    #[serde(default)]
+    pub camera: Coord,
+    // Keys still on the board, doors still locked and which switches have been
+    // flipped an odd number of times, so "Continue" restores every gate.
+    #[serde(default)]
+    pub keys: Vec<Coord>,
+    #[serde(default)]
+    pub keys_held: u64,
+    #[serde(default)]
+    pub doors: Vec<Coord>,
+    #[serde(default)]
+    pub switches_flipped: Vec<bool>,
+    // Steps left at double speed after a speed pad, and whether a sticky tile
+    // holds the snake for the next step.
+    #[serde(default)]
+    pub speed_boost: u64,
+    #[serde(default)]
+    pub stuck: bool,
 }
 
-pub static LEVELS: [&[Coord]; 5] = [
-    &[],
-    &[
-        (0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (0, 6), (0, 7), (0, 8), (0, 9), (0, 10), (0, 11), (0, 12), (0, 13), (0, 14),
-        (14, 0), (14, 1), (14, 2), (14, 3), (14, 4), (14, 5), (14, 6), (14, 7), (14, 8), (14, 9), (14, 10), (14, 11), (14, 12), (14, 13), (14, 14),
-        (1, 0), (2, 0), (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0), (11, 0), (12, 0), (13, 0),
-        (1, 14), (2, 14), (3, 14), (4, 14), (5, 14), (6, 14), (7, 14), (8, 14), (9, 14), (10, 14), (11, 14), (12, 14), (13, 14),
-    ],
-    &[
-        (6, 0), (6, 1), (6, 2), (6, 3), (6, 4), (6, 5), (6, 6), (6, 7), (6, 8), (6, 9), (6,10), (6,11), (6,12), (6,13), (6,14),
-        (7, 0), (7, 1), (7, 2), (7, 3), (7, 4), (7, 5), (7, 6), (7, 7), (7, 8), (7, 9), (7,10), (7,11), (7,12), (7,13), (7,14),
-        (8, 0), (8, 1), (8, 2), (8, 3), (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (8, 9), (8,10), (8,11), (8,12), (8,13), (8,14),
-        (0, 6), (1, 6), (2, 6), (3, 6), (4, 6), (5, 6), (9, 6), (10,6), (11,6), (12,6), (13,6), (14,6),
-        (0, 7), (1, 7), (2, 7), (3, 7), (4, 7), (5, 7), (9, 7), (10,7), (11,7), (12,7), (13,7), (14,7),
-        (0, 8), (1, 8), (2, 8), (3, 8), (4, 8), (5, 8), (9, 8), (10,8), (11,8), (12,8), (13,8), (14,8),
-        (0, 0), (0, 1), (0, 2), (1, 0), (2, 0),
-        (0, 12), (0, 13), (0, 14), (1, 14), (2, 14),
-        (12, 0), (13, 0), (14, 0), (14, 1), (14, 2),
-        (12, 14), (13, 14), (14, 14), (14, 12), (14, 13),
-    ],
-    &[
-        (0, 6), (0, 7), (0, 8),
-        (1, 6), (1, 7), (1, 8),
-        (2, 6), (2, 7), (2, 8),
-        (3, 6), (3, 7), (3, 8),
-        (4, 6), (4, 7), (4, 8),
-        (5, 6), (5, 7), (5, 8),
-        (6, 6), (6, 7), (6, 8),
-        (7, 6), (7, 7), (7, 8),
-        (8, 6), (8, 7), (8, 8),
-        (9, 6), (9, 7), (9, 8),
-        (10, 6), (10, 7), (10, 8),
-        (11, 6), (11, 7), (11, 8),
-        (12, 6), (12, 7), (12, 8),
-        (13, 6), (13, 7), (13, 8),
-        (14, 6), (14, 7), (14, 8),
-    ],
-    &[
-        (5, 5), (5, 6), (5, 7), (5, 8), (5, 9),
-        (6, 5), (6, 6), (6, 7), (6, 8), (6, 9),
-        (7, 5), (7, 6), (7, 7), (7, 8), (7, 9),
-        (8, 5), (8, 6), (8, 7), (8, 8), (8, 9),
-        (9, 5), (9, 6), (9, 7), (9, 8), (9, 9),
-    ],
-];
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Food {
+    pub kind: FoodKindsEnum,
+    pub position: Coord,
+    pub expires_in: Option<u64>,
+}
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct PowerUp {
+    pub kind: PowerUpsEnum,
+    pub position: Coord,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ActiveEffect {
+    pub kind: PowerUpsEnum,
+    pub remaining: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub author: String,
+    pub width: usize,
+    pub height: usize,
+    pub start: Coord,
+    pub start_direction: DirectionsEnum,
+    pub walls: Vec<Coord>,
+    #[serde(default)]
+    pub edges: EdgesEnum,
+    #[serde(default)]
+    pub portals: Vec<(Coord, Coord)>,
+    // The digit each pair of `portals` is marked with in the map.
+    #[serde(default)]
+    pub portal_digits: Vec<usize>,
+    #[serde(default)]
+    pub hazards: Vec<HazardDefinition>,
+    #[serde(default)]
+    pub food_table: Vec<FoodSpawn>,
+    #[serde(default)]
+    pub puzzle_food: Vec<Coord>,
+    #[serde(default)]
+    pub move_limit: Option<u64>,
+    #[serde(default)]
+    pub keys: Vec<Coord>,
+    #[serde(default)]
+    pub doors: Vec<Coord>,
+    #[serde(default)]
+    pub switches: Vec<SwitchDefinition>,
+    #[serde(default)]
+    pub tiles: Vec<TileDefinition>,
+    // Where a built-in or generated level came from, so it can be shared as
+    // part of a challenge code. Custom and edited levels have none.
+    #[serde(default)]
+    pub source: Option<LevelSource>,
+}
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum HazardDefinition {
+    Patrol { path: Vec<Coord>, every: u64 },
+    Bounce { start: Coord, direction: DirectionsEnum, every: u64 },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TileDefinition {
+    Ice { cells: Vec<Coord> },
+    Conveyor { cells: Vec<Coord>, direction: DirectionsEnum },
+    SpeedPad { cells: Vec<Coord> },
+    Sticky { cells: Vec<Coord> },
+}
+
+// Entering `position` flips the switch, which opens its gates when they are
+// closed and closes them when they are open. `open` is how the gates start.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SwitchDefinition {
+    pub position: Coord,
+    pub gates: Vec<Coord>,
+    #[serde(default)]
+    pub open: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Hazard {
+    pub position: Coord,
+    pub path: Vec<Coord>,
+    pub path_index: usize,
+    pub reversed: bool,
+    pub direction: DirectionsEnum,
+    pub every: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct LevelFile {
+    pub name: String,
+    pub author: String,
+    pub width: usize,
+    pub height: usize,
+    pub start_direction: DirectionsEnum,
+    #[serde(default)]
+    pub edges: EdgesEnum,
+    pub map: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hazards: Vec<HazardDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub food: Vec<FoodSpawn>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moves: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub switches: Vec<SwitchDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<TileDefinition>,
+}
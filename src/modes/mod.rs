@@ -0,0 +1,54 @@
+use std::time::Duration;
+use crate::fog;
+use crate::puzzle;
+use crate::models::{GameModesEnum, GameState, Level, TIME_ATTACK_FOOD_BONUS_MS, TIME_ATTACK_LIMIT_MS};
+
+pub fn new_game(difficulty: u16, mode: GameModesEnum, level: &Level, seed: Option<u64>) -> GameState {
+    let mut game_state = GameState::new_seeded(difficulty, level, seed);
+
+    game_state.mode = mode;
+
+    match mode {
+        GameModesEnum::TimeAttack => game_state.time_limit = Some(TIME_ATTACK_LIMIT_MS),
+        GameModesEnum::Puzzle => puzzle::start_puzzle(&mut game_state),
+        GameModesEnum::Fog => fog::remember_walls(&mut game_state),
+        _ => {}
+    }
+
+    game_state
+}
+
+// Zen and puzzle games and daily challenge replays are kept off the leaderboard
+// and out of the stats.
+pub fn is_ranked(game_state: &GameState) -> bool {
+    match game_state.mode {
+        GameModesEnum::Classic | GameModesEnum::TimeAttack | GameModesEnum::Survival | GameModesEnum::Fog => true,
+        GameModesEnum::Daily => game_state.daily.is_some_and(|daily| daily.ranked),
+        GameModesEnum::Zen | GameModesEnum::Puzzle => false,
+    }
+}
+
+pub fn tick_clock(game_state: &mut GameState, elapsed: Duration) {
+    game_state.play_time += elapsed.as_millis() as u64;
+}
+
+pub fn add_food_time(game_state: &mut GameState) {
+    if let Some(limit) = game_state.time_limit.as_mut() {
+        *limit += TIME_ATTACK_FOOD_BONUS_MS;
+    }
+}
+
+pub fn get_time_left(game_state: &GameState) -> Option<u64> {
+    game_state.time_limit.map(|limit| limit.saturating_sub(game_state.play_time))
+}
+
+pub fn is_time_up(game_state: &GameState) -> bool {
+    get_time_left(game_state) == Some(0)
+}
+
+// Rounded up, so the countdown only shows 0:00 once the time is really up.
+pub fn format_time(ms: u64) -> String {
+    let seconds = ms.div_ceil(1000);
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -0,0 +1,132 @@
+use crate::hazards::get_opposite_direction;
+use crate::models::{Coord, DirectionsEnum, GameState, ModifierRule, ModifiersEnum, MODIFIER_RULES, MOVING_FOOD_EVERY, VISION_RADIUS};
+
+pub fn get_rule(kind: ModifiersEnum) -> &'static ModifierRule {
+    MODIFIER_RULES.iter().find(|rule| rule.kind == kind).unwrap()
+}
+
+pub fn has_modifier(game_state: &GameState, kind: ModifiersEnum) -> bool {
+    game_state.modifiers.contains(&kind)
+}
+
+pub fn toggle_modifier(modifiers: &mut Vec<ModifiersEnum>, kind: ModifiersEnum) {
+    if modifiers.contains(&kind) {
+        modifiers.retain(|modifier| *modifier != kind);
+    } else {
+        // Kept in rule order, so the same set always gets the same tags.
+        modifiers.push(kind);
+        modifiers.sort_by_key(|modifier| MODIFIER_RULES.iter().position(|rule| rule.kind == *modifier));
+    }
+}
+
+// The multipliers of all active modifiers stack.
+pub fn get_score_percent(modifiers: &[ModifiersEnum]) -> u64 {
+    modifiers.iter().fold(100, |percent, modifier| percent * get_rule(*modifier).score_percent / 100)
+}
+
+pub fn apply_score(game_state: &GameState, points: u64) -> u64 {
+    points * get_score_percent(&game_state.modifiers) / 100
+}
+
+pub fn get_direction(game_state: &GameState, direction: DirectionsEnum) -> DirectionsEnum {
+    if has_modifier(game_state, ModifiersEnum::MirroredControls) { get_opposite_direction(direction) } else { direction }
+}
+
+pub fn get_tick_divisor(game_state: &GameState) -> u64 {
+    if has_modifier(game_state, ModifiersEnum::DoubleSpeed) { 2 } else { 1 }
+}
+
+pub fn is_food_moving(game_state: &GameState) -> bool {
+    has_modifier(game_state, ModifiersEnum::MovingFood) && game_state.steps.is_multiple_of(MOVING_FOOD_EVERY)
+}
+
+pub fn is_body_hidden(game_state: &GameState) -> bool {
+    has_modifier(game_state, ModifiersEnum::InvisibleBody)
+}
+
+pub fn is_visible(game_state: &GameState, coord: Coord) -> bool {
+    if !has_modifier(game_state, ModifiersEnum::LimitedVision) { return true; }
+
+    let head = game_state.snake_body[0];
+
+    head.0.abs_diff(coord.0) <= VISION_RADIUS && head.1.abs_diff(coord.1) <= VISION_RADIUS
+}
+
+pub fn format_tags(modifiers: &[ModifiersEnum]) -> String {
+    modifiers.iter().map(|modifier| get_rule(*modifier).tag).collect()
+}
+
+pub fn parse_tags(tags: &str) -> Option<Vec<ModifiersEnum>> {
+    let mut modifiers = Vec::new();
+
+    for tag in tags.chars() {
+        let rule = MODIFIER_RULES.iter().find(|rule| rule.tag == tag)?;
+
+        if !modifiers.contains(&rule.kind) { toggle_modifier(&mut modifiers, rule.kind); }
+    }
+
+    Some(modifiers)
+}
+
+pub fn get_summary(modifiers: &[ModifiersEnum]) -> String {
+    let percent = get_score_percent(modifiers);
+
+    format!("Mods: {}   Score x{}.{:02}", format_tags(modifiers), percent / 100, percent % 100)
+}
+
+pub fn get_menu_label(modifiers: &[ModifiersEnum], rule: &ModifierRule) -> String {
+    let mark = if modifiers.contains(&rule.kind) { 'x' } else { ' ' };
+
+    format!("[{}] {} x{}.{:02}", mark, rule.label, rule.score_percent / 100, rule.score_percent % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Level;
+
+    fn get_game_state(modifiers: &[ModifiersEnum]) -> GameState {
+        let mut game_state = GameState::new(1, &Level::default());
+
+        game_state.modifiers = modifiers.to_vec();
+        game_state
+    }
+
+    #[test]
+    fn tags_round_trip() {
+        for mask in 0..(1 << MODIFIER_RULES.len()) {
+            let mut modifiers = Vec::new();
+
+            for (index, rule) in MODIFIER_RULES.iter().enumerate().rev() {
+                if mask & (1 << index) != 0 { toggle_modifier(&mut modifiers, rule.kind); }
+            }
+
+            assert_eq!(parse_tags(&format_tags(&modifiers)), Some(modifiers));
+        }
+    }
+
+    #[test]
+    fn unknown_tags_are_rejected() {
+        assert_eq!(parse_tags("IQ"), None);
+        assert_eq!(parse_tags("VI"), Some(vec![ModifiersEnum::InvisibleBody, ModifiersEnum::LimitedVision]));
+    }
+
+    #[test]
+    fn score_percents_stack() {
+        let all: Vec<ModifiersEnum> = MODIFIER_RULES.iter().map(|rule| rule.kind).collect();
+
+        assert_eq!(apply_score(&get_game_state(&[]), 10), 10);
+        assert_eq!(apply_score(&get_game_state(&[ModifiersEnum::InvisibleBody, ModifiersEnum::LimitedVision]), 10), 21);
+        assert_eq!(get_score_percent(&all), 539);
+        assert_eq!(apply_score(&get_game_state(&all), 100), 539);
+    }
+
+    #[test]
+    fn mirrored_controls_flip_the_direction() {
+        let mirrored = get_game_state(&[ModifiersEnum::MirroredControls]);
+
+        assert_eq!(get_direction(&get_game_state(&[]), DirectionsEnum::Up), DirectionsEnum::Up);
+        assert_eq!(get_direction(&mirrored, DirectionsEnum::Up), DirectionsEnum::Down);
+        assert_eq!(get_direction(&mirrored, DirectionsEnum::Left), DirectionsEnum::Right);
+    }
+}
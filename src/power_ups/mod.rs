@@ -0,0 +1,112 @@
+use crate::game::shrink_snake;
+use crate::hazards::get_hazard_zone;
+use crate::levels::get_neighbour;
+use crate::models::{ActiveEffect, Coord, DirectionsEnum, FoodKindsEnum, GameState, PowerUp, PowerUpRule, PowerUpsEnum, POWER_UP_LIFETIME, POWER_UP_RULES, POWER_UP_SPAWN_CHANCE, SHRINK_AMOUNT};
+use crate::utils::get_game_random_int;
+
+pub fn get_rule(kind: PowerUpsEnum) -> &'static PowerUpRule {
+    POWER_UP_RULES.iter().find(|rule| rule.kind == kind).unwrap()
+}
+
+pub fn has_effect(game_state: &GameState, kind: PowerUpsEnum) -> bool {
+    game_state.effects.iter().any(|effect| effect.kind == kind)
+}
+
+pub fn get_score_multiplier(game_state: &GameState) -> u64 {
+    if has_effect(game_state, PowerUpsEnum::Multiplier) { 2 } else { 1 }
+}
+
+pub fn get_tick_multiplier(game_state: &GameState) -> u64 {
+    if has_effect(game_state, PowerUpsEnum::SlowMotion) { 2 } else { 1 }
+}
+
+pub fn tick_power_ups(game_state: &mut GameState) {
+    for effect in game_state.effects.iter_mut() {
+        effect.remaining = effect.remaining.saturating_sub(1);
+    }
+
+    game_state.effects.retain(|effect| effect.remaining > 0);
+
+    if let Some(power_up) = game_state.power_up.as_mut() {
+        power_up.expires_in = power_up.expires_in.saturating_sub(1);
+
+        if power_up.expires_in == 0 {
+            game_state.power_up = None;
+        }
+    }
+}
+
+pub fn collect_power_up(game_state: &mut GameState, head: Coord) {
+    let Some(power_up) = game_state.power_up.take_if(|power_up| power_up.position == head) else { return; };
+    let rule = get_rule(power_up.kind);
+
+    if power_up.kind == PowerUpsEnum::Shrink {
+        shrink_snake(game_state, SHRINK_AMOUNT);
+        return;
+    }
+
+    game_state.effects.retain(|effect| effect.kind != power_up.kind);
+    game_state.effects.push(ActiveEffect { kind: power_up.kind, remaining: rule.duration });
+}
+
+pub fn spawn_power_up(game_state: &mut GameState, free_cell: Option<Coord>) {
+    if game_state.power_up.is_some() || get_game_random_int(&mut game_state.rng, 0, POWER_UP_SPAWN_CHANCE) != 0 { return; }
+
+    let Some(position) = free_cell else { return; };
+    let available: Vec<&PowerUpRule> = POWER_UP_RULES
+        .iter()
+        .filter(|rule| game_state.food_eaten >= rule.min_food)
+        .collect();
+
+    let total_weight: usize = available.iter().map(|rule| rule.weight).sum();
+
+    if total_weight == 0 { return; }
+
+    let mut roll = get_game_random_int(&mut game_state.rng, 0, total_weight);
+
+    for rule in available {
+        if roll < rule.weight {
+            game_state.power_up = Some(PowerUp { kind: rule.kind, position, expires_in: POWER_UP_LIFETIME });
+            return;
+        }
+
+        roll -= rule.weight;
+    }
+}
+
+// The magnet drags every edible item one cell closer to the head on every step,
+// poison is left where it is.
+pub fn pull_food(game_state: &mut GameState) {
+    if !has_effect(game_state, PowerUpsEnum::Magnet) { return; }
+
+    let head = game_state.snake_body[0];
+    let hazard_zone = get_hazard_zone(game_state);
+    let distance = |(i, j): Coord| i.abs_diff(head.0) + j.abs_diff(head.1);
+    let directions = [DirectionsEnum::Up, DirectionsEnum::Down, DirectionsEnum::Left, DirectionsEnum::Right];
+    let mut taken: Vec<Coord> = game_state.food.iter().map(|food| food.position).collect();
+
+    for index in 0..game_state.food.len() {
+        let food = &game_state.food[index];
+
+        if food.kind == FoodKindsEnum::Poison { continue; }
+
+        let next = directions
+            .iter()
+            .filter_map(|direction| get_neighbour(&game_state.level, food.position, *direction))
+            .filter(|cell| distance(*cell) < distance(food.position))
+            .find(|cell| game_state.grid[cell.0][cell.1] == 0 && !hazard_zone.contains(cell) && !taken.contains(cell));
+
+        if let Some(next) = next {
+            taken[index] = next;
+            game_state.food[index].position = next;
+        }
+    }
+}
+
+pub fn get_effects_summary(game_state: &GameState) -> String {
+    game_state.effects
+        .iter()
+        .map(|effect| format!("{} {}", get_rule(effect.kind).label, effect.remaining))
+        .collect::<Vec<String>>()
+        .join("  ")
+}
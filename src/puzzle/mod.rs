@@ -0,0 +1,148 @@
+use crate::food;
+use crate::levels::{get_puzzle_food_char, is_puzzle};
+use crate::models::{AppState, Coord, FoodKindsEnum, GameState, Level, Puzzle, PuzzleRecord, FOOD, PUZZLE_FOOD_POINTS};
+
+// Replaces the random food with the level's puzzle food, all of it on the board
+// from the first move.
+pub fn start_puzzle(game_state: &mut GameState) {
+    game_state.food.clear();
+
+    for (x, y) in game_state.level.puzzle_food.clone() {
+        food::add_food(game_state, FoodKindsEnum::Normal, Some((x, y)));
+        game_state.grid[x][y] = FOOD;
+    }
+
+    game_state.puzzle = Some(Puzzle { next: 0, moves: 0 });
+}
+
+// Counts the move and eats the puzzle food under the head. Returns `Some(true)`
+// once the puzzle is solved, `Some(false)` when food was eaten out of order or
+// the moves ran out, and `None` while it is still going.
+pub fn update_puzzle(game_state: &mut GameState, head: Coord) -> Option<bool> {
+    let mut puzzle = game_state.puzzle?;
+
+    puzzle.moves += 1;
+
+    if let Some(eaten) = food::take_food_at(game_state, head) {
+        if game_state.level.puzzle_food.get(puzzle.next) != Some(&eaten.position) {
+            game_state.puzzle = Some(puzzle);
+            return Some(false);
+        }
+
+        puzzle.next += 1;
+        game_state.score += PUZZLE_FOOD_POINTS;
+        game_state.food_eaten += 1;
+        game_state.pending_growth += 1;
+    }
+
+    let level_food = game_state.level.puzzle_food.len();
+    let move_limit = game_state.level.move_limit.unwrap_or(0);
+
+    game_state.puzzle = Some(puzzle);
+
+    if puzzle.next == level_food { return Some(true); }
+    if puzzle.moves >= move_limit { return Some(false); }
+
+    None
+}
+
+pub fn get_food_char(game_state: &GameState, coord: Coord) -> Option<char> {
+    game_state.puzzle?;
+
+    game_state.level.puzzle_food
+        .iter()
+        .position(|food| *food == coord)
+        .map(get_puzzle_food_char)
+}
+
+pub fn get_summary(puzzle: &Puzzle, level: &Level) -> String {
+    let next = if puzzle.next < level.puzzle_food.len() { get_puzzle_food_char(puzzle.next) } else { '-' };
+
+    format!("Moves: {}/{}   Next: {}", puzzle.moves, level.move_limit.unwrap_or(0), next)
+}
+
+pub fn get_best_moves(app_state: &AppState, level: &Level) -> Option<u64> {
+    app_state.puzzle_records
+        .iter()
+        .find(|record| record.name == level.name)
+        .map(|record| record.moves)
+}
+
+// Keeps the fewest moves each puzzle was solved in.
+pub fn save_record(app_state: &mut AppState, level: &Level, moves: u64) {
+    if !is_puzzle(level) { return; }
+
+    match app_state.puzzle_records.iter_mut().find(|record| record.name == level.name) {
+        Some(record) => record.moves = record.moves.min(moves),
+        None => app_state.puzzle_records.push(PuzzleRecord { name: level.name.clone(), moves }),
+    }
+
+    app_state.dirty = true;
+}
+
+pub fn get_menu_label(app_state: &AppState, level: &Level) -> String {
+    match get_best_moves(app_state, level) {
+        Some(moves) => format!("{} [best {}]", level.name, moves),
+        None => format!("{} [unsolved]", level.name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::parse_level;
+    use crate::modes;
+    use crate::models::GameModesEnum;
+
+    fn start(row: &str, moves: u64) -> GameState {
+        let content = format!(
+            r#"{{ "name": "Test", "author": "Test", "width": {}, "height": 2, "start_direction": "Right", "edges": "Solid", "moves": {}, "map": ["{}", "{}"] }}"#,
+            row.len(), moves, row, ".".repeat(row.len()),
+        );
+
+        modes::new_game(1, GameModesEnum::Puzzle, &parse_level(&content).unwrap(), None)
+    }
+
+    #[test]
+    fn eating_out_of_order_fails() {
+        let mut game_state = start("S.ba.", 10);
+
+        assert_eq!(update_puzzle(&mut game_state, (0, 1)), None);
+        assert_eq!(update_puzzle(&mut game_state, (0, 2)), Some(false));
+    }
+
+    #[test]
+    fn running_out_of_moves_fails() {
+        let mut game_state = start("S...a", 3);
+
+        assert_eq!(update_puzzle(&mut game_state, (0, 1)), None);
+        assert_eq!(update_puzzle(&mut game_state, (0, 2)), None);
+        assert_eq!(update_puzzle(&mut game_state, (0, 3)), Some(false));
+    }
+
+    #[test]
+    fn eating_the_last_food_on_the_last_move_solves() {
+        let mut game_state = start("Sab..", 2);
+
+        assert_eq!(update_puzzle(&mut game_state, (0, 1)), None);
+        assert_eq!(update_puzzle(&mut game_state, (0, 2)), Some(true));
+        assert_eq!(game_state.score, 2 * PUZZLE_FOOD_POINTS);
+        assert_eq!(game_state.puzzle, Some(Puzzle { next: 2, moves: 2 }));
+    }
+
+    #[test]
+    fn records_keep_the_fewest_moves() {
+        let mut app_state = crate::init_game_state().app_state;
+        let level = start("S.a..", 10).level;
+
+        app_state.puzzle_records.clear();
+
+        for (moves, best) in [(7, 7), (9, 7), (5, 5)] {
+            save_record(&mut app_state, &level, moves);
+
+            assert_eq!(get_best_moves(&app_state, &level), Some(best));
+        }
+
+        assert_eq!(app_state.puzzle_records.len(), 1);
+    }
+}
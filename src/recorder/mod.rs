@@ -0,0 +1,75 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use serde_json::json;
+use crate::models::{AppState, CastRecorder};
+use crate::utils::get_terminal_size;
+
+// Sessions are written in the asciinema v2 format: a JSON header line followed
+// by one `[time, code, data]` JSON array per line.
+pub fn start_recording(path: &str) -> io::Result<CastRecorder> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    let (width, height) = get_terminal_size();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let header = json!({
+        "version": 2,
+        "width": width,
+        "height": height,
+        "timestamp": timestamp,
+        "title": "Snake Game",
+        "env": {
+            "TERM": std::env::var("TERM").unwrap_or(String::from("xterm-256color")),
+        },
+    });
+
+    writeln!(writer, "{}", header)?;
+
+    Ok(CastRecorder {
+        writer,
+        started_at: Instant::now(),
+        terminal_size: (width, height),
+    })
+}
+
+pub fn record_output(recorder: &mut CastRecorder, frame: &[u8]) -> io::Result<()> {
+    let terminal_size = get_terminal_size();
+
+    if terminal_size != recorder.terminal_size {
+        let size = format!("{}x{}", terminal_size.0, terminal_size.1);
+
+        write_event(recorder, "r", &size)?;
+        recorder.terminal_size = terminal_size;
+    }
+
+    write_event(recorder, "o", &String::from_utf8_lossy(frame))
+}
+
+// Markers let a player jump between games when the recording is replayed.
+pub fn record_marker(recorder: &mut CastRecorder, label: &str) -> io::Result<()> {
+    write_event(recorder, "m", label)
+}
+
+pub fn stop_recording(recorder: &mut CastRecorder) -> io::Result<()> {
+    recorder.writer.flush()
+}
+
+// A failed write (a full disk, a removed file) ends the recording but not the
+// game; the error is printed once the terminal is back to normal.
+pub fn check_write(app_state: &mut AppState, result: io::Result<()>) {
+    let Err(error) = result else { return; };
+
+    app_state.recorder = None;
+    app_state.recording_error = Some(error.to_string());
+}
+
+fn write_event(recorder: &mut CastRecorder, code: &str, data: &str) -> io::Result<()> {
+    let time = recorder.started_at.elapsed().as_secs_f64();
+    let event = json!([(time * 1_000_000.0).round() / 1_000_000.0, code, data]);
+
+    writeln!(recorder.writer, "{}", event)
+}
@@ -0,0 +1,63 @@
+use crate::hazards::get_hazard_cells;
+use crate::levels::{get_reachable_cells, get_step};
+use crate::models::{Coord, GameModesEnum, GameState, PORTAL, SURVIVAL_WALL_EVERY, SURVIVAL_WALL_POINTS, WALL};
+use crate::triggers::get_item_cells;
+use crate::utils::get_game_random_int;
+
+// Survival walls only go into the grid, the level itself keeps its original
+// layout so a new game starts from a clean board.
+pub fn spawn_wall(game_state: &mut GameState) {
+    if game_state.mode != GameModesEnum::Survival || !game_state.steps.is_multiple_of(SURVIVAL_WALL_EVERY) { return; }
+
+    let mut candidates = get_candidates(game_state);
+
+    while !candidates.is_empty() {
+        let (i, j) = candidates.swap_remove(get_game_random_int(&mut game_state.rng, 0, candidates.len()));
+
+        game_state.grid[i][j] = WALL;
+
+        if keeps_board_connected(game_state) {
+            game_state.score += SURVIVAL_WALL_POINTS;
+            return;
+        }
+
+        game_state.grid[i][j] = 0;
+    }
+}
+
+pub fn get_steps_to_next_wall(game_state: &GameState) -> u64 {
+    SURVIVAL_WALL_EVERY - game_state.steps % SURVIVAL_WALL_EVERY
+}
+
+// Free cells away from the snake's next move and from anything a hazard can reach.
+fn get_candidates(game_state: &GameState) -> Vec<Coord> {
+    let level = &game_state.level;
+    let head = game_state.snake_body[0];
+    let mut excluded: Vec<Coord> = level.hazards.iter().flat_map(|hazard| get_hazard_cells(level, hazard)).collect();
+
+    excluded.extend(get_step(level, head, game_state.snake_direction));
+    excluded.extend(get_step(level, head, game_state.next_direction));
+    excluded.extend(get_item_cells(game_state));
+
+    let mut candidates = Vec::new();
+
+    for (i, row) in game_state.grid.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if *cell == 0 && !excluded.contains(&(i, j)) {
+                candidates.push((i, j));
+            }
+        }
+    }
+
+    candidates
+}
+
+// Like the level validator: every cell that is not a wall or a portal has to
+// stay reachable from the head.
+fn keeps_board_connected(game_state: &GameState) -> bool {
+    let grid = &game_state.grid;
+    let is_free = |(i, j): Coord| grid[i][j] != WALL && grid[i][j] != PORTAL;
+    let free_cells = grid.iter().flatten().filter(|cell| **cell != WALL && **cell != PORTAL).count();
+
+    get_reachable_cells(&game_state.level, game_state.snake_body[0], &is_free).len() == free_cells
+}
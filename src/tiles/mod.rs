@@ -0,0 +1,128 @@
+use crate::hazards::get_opposite_direction;
+use crate::models::{Coord, DirectionsEnum, GameState, Level, TileDefinition, SPEED_PAD_STEPS};
+
+pub fn get_tile(level: &Level, coord: Coord) -> Option<&TileDefinition> {
+    level.tiles.iter().find(|tile| get_tile_cells(tile).contains(&coord))
+}
+
+pub fn get_tile_cells(tile: &TileDefinition) -> &[Coord] {
+    match tile {
+        TileDefinition::Ice { cells } |
+        TileDefinition::Conveyor { cells, .. } |
+        TileDefinition::SpeedPad { cells } |
+        TileDefinition::Sticky { cells } => cells,
+    }
+}
+
+// The snake cannot turn while its head is on ice; the turn waits until it is off.
+pub fn is_on_ice(game_state: &GameState) -> bool {
+    matches!(get_tile(&game_state.level, game_state.snake_body[0]), Some(TileDefinition::Ice { .. }))
+}
+
+// A conveyor moves the head one cell its way on the next step, without turning
+// the snake. One running against the snake would push the head onto its own
+// neck, so the snake rides over it instead.
+pub fn get_move_direction(game_state: &GameState) -> DirectionsEnum {
+    match get_tile(&game_state.level, game_state.snake_body[0]) {
+        Some(TileDefinition::Conveyor { direction, .. })
+            if *direction != get_opposite_direction(game_state.snake_direction) => *direction,
+        _ => game_state.snake_direction,
+    }
+}
+
+// Run once the head has moved into `coord`.
+pub fn enter_tile(game_state: &mut GameState, coord: Coord) {
+    match get_tile(&game_state.level, coord) {
+        Some(TileDefinition::SpeedPad { .. }) => game_state.speed_boost = SPEED_PAD_STEPS,
+        Some(TileDefinition::Sticky { .. }) => game_state.stuck = true,
+        _ => {}
+    }
+}
+
+// Returns true when the snake sits out this step because it is stuck. The rest
+// of the world still moves on.
+pub fn hold_snake(game_state: &mut GameState) -> bool {
+    if !game_state.stuck { return false; }
+
+    game_state.stuck = false;
+    true
+}
+
+pub fn tick_tiles(game_state: &mut GameState) {
+    game_state.speed_boost = game_state.speed_boost.saturating_sub(1);
+}
+
+pub fn get_tick_divisor(game_state: &GameState) -> u64 {
+    if game_state.speed_boost > 0 { 2 } else { 1 }
+}
+
+pub fn get_symbol(level: &Level, coord: Coord) -> Option<&'static str> {
+    let symbol = match get_tile(level, coord)? {
+        TileDefinition::Ice { .. } => "~ ",
+        TileDefinition::Conveyor { direction: DirectionsEnum::Up, .. } => "↑ ",
+        TileDefinition::Conveyor { direction: DirectionsEnum::Down, .. } => "↓ ",
+        TileDefinition::Conveyor { direction: DirectionsEnum::Left, .. } => "← ",
+        TileDefinition::Conveyor { direction: DirectionsEnum::Right, .. } => "→ ",
+        TileDefinition::SpeedPad { .. } => "» ",
+        TileDefinition::Sticky { .. } => "% ",
+    };
+
+    Some(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::parse_level;
+
+    // The snake starts right before a row of conveyors running `direction` and
+    // faces right, onto them.
+    fn start_on_conveyor(direction: &str) -> GameState {
+        let content = format!(
+            r#"{{ "name": "Test", "author": "Test", "width": 5, "height": 3, "start_direction": "Right", "edges": "Solid",
+                "map": [".....", "S....", "....."], "tiles": [{{ "type": "Conveyor", "cells": [[1, 1], [1, 2]], "direction": "{}" }}] }}"#,
+            direction,
+        );
+
+        GameState::new_seeded(1, &parse_level(&content).unwrap(), Some(1))
+    }
+
+    #[test]
+    fn conveyor_pushes_the_head_its_way() {
+        let mut game_state = start_on_conveyor("Up");
+
+        game_state.snake_body = vec![(1, 1), (1, 0)];
+
+        assert_eq!(get_move_direction(&game_state), DirectionsEnum::Up);
+    }
+
+    #[test]
+    fn backwards_conveyor_is_ridden_over() {
+        let mut game_state = start_on_conveyor("Left");
+
+        game_state.snake_body = vec![(1, 1), (1, 0)];
+
+        assert_eq!(get_move_direction(&game_state), DirectionsEnum::Right);
+    }
+
+    #[test]
+    fn sticky_floor_holds_for_one_step() {
+        let mut game_state = start_on_conveyor("Up");
+
+        game_state.stuck = true;
+
+        assert!(hold_snake(&mut game_state));
+        assert!(!hold_snake(&mut game_state));
+    }
+
+    #[test]
+    fn speed_pad_doubles_the_speed_until_it_runs_out() {
+        let mut game_state = start_on_conveyor("Up");
+
+        game_state.speed_boost = 1;
+        assert_eq!(get_tick_divisor(&game_state), 2);
+
+        tick_tiles(&mut game_state);
+        assert_eq!(get_tick_divisor(&game_state), 1);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::models::{Coord, GameState, Level, WALL};
+
+// Locked doors and closed gates are walls in the grid, so everything that
+// avoids walls (collisions, spawning, line of sight) handles them for free.
+pub fn get_closed_cells(level: &Level) -> Vec<Coord> {
+    let mut cells = level.doors.clone();
+
+    for switch in level.switches.iter().filter(|switch| !switch.open) {
+        cells.extend(&switch.gates);
+    }
+
+    cells
+}
+
+// Run before the snake moves into `coord`: a held key is used up to unlock the
+// door there, otherwise the door stays a wall.
+pub fn unlock_door(game_state: &mut GameState, coord: Coord) {
+    if game_state.keys_held == 0 || !game_state.doors.contains(&coord) { return; }
+
+    game_state.keys_held -= 1;
+    game_state.doors.retain(|door| *door != coord);
+    game_state.grid[coord.0][coord.1] = 0;
+}
+
+// Run once the head has moved into `coord`: picks up a key or flips a switch.
+pub fn enter_cell(game_state: &mut GameState, coord: Coord) {
+    if game_state.keys.contains(&coord) {
+        game_state.keys.retain(|key| *key != coord);
+        game_state.keys_held += 1;
+    }
+
+    if let Some(index) = game_state.level.switches.iter().position(|switch| switch.position == coord) {
+        game_state.switches_flipped[index] = !game_state.switches_flipped[index];
+    }
+}
+
+// Brings the grid in line with the switches. A gate that should close while
+// the snake is in it stays open until the snake has left the cell; food or a
+// power-up caught in a closing gate is lost.
+pub fn update_gates(game_state: &mut GameState) {
+    for index in 0..game_state.level.switches.len() {
+        let switch = &game_state.level.switches[index];
+        let closed = switch.open == game_state.switches_flipped[index];
+
+        for (i, j) in switch.gates.clone() {
+            if !closed {
+                if game_state.grid[i][j] == WALL { game_state.grid[i][j] = 0; }
+                continue;
+            }
+
+            if game_state.snake_body.contains(&(i, j)) { continue; }
+
+            game_state.food.retain(|food| food.position != (i, j));
+            game_state.power_up = game_state.power_up.take().filter(|power_up| power_up.position != (i, j));
+            game_state.grid[i][j] = WALL;
+        }
+    }
+}
+
+// Cells with keys, switches or open gates, which food and walls must not take.
+pub fn get_item_cells(game_state: &GameState) -> Vec<Coord> {
+    let mut cells = game_state.keys.clone();
+
+    for switch in &game_state.level.switches {
+        cells.push(switch.position);
+        cells.extend(&switch.gates);
+    }
+
+    cells
+}
+
+pub fn get_symbol(game_state: &GameState, coord: Coord) -> Option<&'static str> {
+    if game_state.doors.contains(&coord) { return Some("D "); }
+    if game_state.keys.contains(&coord) { return Some("k "); }
+
+    for (index, switch) in game_state.level.switches.iter().enumerate() {
+        if switch.position == coord {
+            return Some(if game_state.switches_flipped[index] { "\\ " } else { "/ " });
+        }
+
+        if switch.gates.contains(&coord) {
+            return Some(if game_state.grid[coord.0][coord.1] == WALL { "H " } else { ": " });
+        }
+    }
+
+    None
+}
+
+pub fn get_summary(game_state: &GameState) -> Option<String> {
+    if game_state.level.keys.is_empty() { return None; }
+
+    Some(format!("Keys: {}   Locked doors: {}", game_state.keys_held, game_state.doors.len()))
+}
@@ -1,54 +1,191 @@
-use std::io::stdout;
+use std::cell::RefCell;
+use std::io::{stdout, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
 use crossterm::cursor::{Hide, MoveToColumn, MoveToRow, Show};
-use crossterm::{execute};
+use crossterm::queue;
 use crossterm::terminal::{size, Clear, ClearType};
 use rand::{random_range};
-use crate::FIELD_SIZE;
+
+thread_local! {
+    static FRAME: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+// Everything the renderer draws is collected in the frame buffer first, so one
+// frame reaches the terminal (and the session recorder) as a single write.
+macro_rules! out {
+    ($($arg:tt)*) => { $crate::utils::write_to_frame(&format!($($arg)*)) };
+}
+
+macro_rules! outln {
+    () => { $crate::utils::write_to_frame("\n") };
+    ($($arg:tt)*) => { $crate::utils::write_to_frame(&format!("{}\n", format!($($arg)*))) };
+}
+
+pub(crate) use out;
+pub(crate) use outln;
+
+pub fn write_to_frame(text: &str) {
+    FRAME.with_borrow_mut(|frame| frame.extend_from_slice(text.as_bytes()));
+}
+
+pub fn take_frame() -> Vec<u8> {
+    FRAME.with_borrow_mut(std::mem::take)
+}
+
+pub fn flush_frame(frame: &[u8]) {
+    let mut stdout = stdout();
+
+    stdout.write_all(frame).unwrap();
+    stdout.flush().unwrap();
+}
 
 pub fn clear_screen() {
-    execute!(stdout(), Clear(ClearType::All)).unwrap();
+    FRAME.with_borrow_mut(|frame| queue!(frame, Clear(ClearType::All)).unwrap());
 }
 
 pub fn set_col_position(col: u16) {
-    execute!(stdout(), MoveToColumn(col)).unwrap();
+    FRAME.with_borrow_mut(|frame| queue!(frame, MoveToColumn(col)).unwrap());
 }
 
 pub fn set_row_position(row: u16) {
-    execute!(stdout(), MoveToRow(row)).unwrap();
+    FRAME.with_borrow_mut(|frame| queue!(frame, MoveToRow(row)).unwrap());
 }
 
 pub fn get_random_int(min: usize, max: usize) -> usize {
     random_range(min..max)
 }
 
-pub fn toggle_cursor_visibility(show_cursor: bool) {
-    if show_cursor {
-        execute!(stdout(), Show).unwrap();
-    } else {
-        execute!(stdout(), Hide).unwrap();
+// Seeded games (daily challenges) draw from their own generator, so the same
+// seed plays out the same way; everything else stays truly random.
+pub fn get_game_random_int(rng: &mut Option<u64>, min: usize, max: usize) -> usize {
+    match rng {
+        Some(seed) => get_seeded_int(seed, min, max),
+        None => get_random_int(min, max),
     }
 }
 
-pub fn get_random_free_cell(grid: & Vec<Vec<u16>>) -> (usize, usize) {
+// SplitMix64: tiny, portable and fully determined by the seed, so the same seed
+// produces the same level (or game) on every machine.
+pub fn next_random(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+    let mut z = *seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    z ^ (z >> 31)
+}
+
+pub fn get_seeded_int(seed: &mut u64, min: usize, max: usize) -> usize {
+    min + (next_random(seed) % (max - min) as u64) as usize
+}
+
+pub fn get_today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86400)
+        .unwrap_or(0)
+}
+
+// Days since 1970-01-01 to a "YYYY-MM-DD" date and back, after Howard Hinnant's
+// civil calendar algorithms.
+pub fn format_date(day: u64) -> String {
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+pub fn parse_date(text: &str) -> Option<u64> {
+    let mut parts = text.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) { return None; }
+
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    // Days past the end of the month ("2023-02-29") would roll over into the next one.
+    u64::try_from(era * 146097 + doe - 719468).ok().filter(|day| format_date(*day) == text)
+}
+
+pub fn get_time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// Kept to 32 bits so challenge codes built from it stay short.
+pub fn get_short_seed() -> u64 {
+    get_time_seed() & 0xFFFF_FFFF
+}
+
+pub fn toggle_cursor_visibility(show_cursor: bool) {
+    FRAME.with_borrow_mut(|frame| {
+        if show_cursor {
+            queue!(frame, Show).unwrap();
+        } else {
+            queue!(frame, Hide).unwrap();
+        }
+    });
+}
+
+pub fn get_random_free_cell(grid: &[Vec<u16>], excluded: &[(usize, usize)], rng: &mut Option<u64>) -> Option<(usize, usize)> {
     let mut free_cells: Vec<(usize, usize)> = Vec::new();
 
-    for i in 0..FIELD_SIZE {
-        for j in 0..FIELD_SIZE {
-            if grid[i][j] == 0 {
+    for (i, row) in grid.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if *cell == 0 && !excluded.contains(&(i, j)) {
                 free_cells.push((i, j));
             }
         }
     }
 
-    free_cells[get_random_int(0, free_cells.len())]
+    if free_cells.is_empty() { return None; }
+
+    Some(free_cells[get_game_random_int(rng, 0, free_cells.len())])
 }
 
 pub fn print_empty_lines(amount: u16) {
-    for _ in 0..amount { println!(); }
+    for _ in 0..amount { outln!(); }
+}
+
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + word.chars().count() + 1 > width {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        if !line.is_empty() { line.push(' '); }
+
+        line.push_str(word);
+    }
+
+    if !line.is_empty() { lines.push(line); }
+
+    lines
+}
+
+pub fn get_terminal_size() -> (u16, u16) {
+    size().unwrap()
 }
 
 pub fn get_view_offset(view_width: u16, view_height: u16) -> (u16, u16) {
-    let (cols, rows) = size().unwrap();
+    let (cols, rows) = get_terminal_size();
 
     let offset_left = cols.saturating_sub(view_width) / 2;
     let offset_top = rows.saturating_sub(view_height) / 2;
@@ -64,9 +201,41 @@ pub fn render_logo(offset_left: u16) {
         "                             ",
     ];
 
-    for i in 0..lines.len() {
-        println!();
+    for line in lines {
+        outln!();
         set_col_position(offset_left);
-        print!("{}", lines[i]);
+        out!("{}", line);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        // From 1970 to past 2400, through the leap years 2000 and 2400 and the
+        // common years 2100, 2200 and 2300.
+        for day in 0..160_000 {
+            assert_eq!(parse_date(&format_date(day)), Some(day), "{}", format_date(day));
+        }
+    }
+
+    #[test]
+    fn leap_days_are_formatted() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(parse_date("2000-02-29"), Some(11_016));
+        assert_eq!(format_date(11_016), "2000-02-29");
+        assert_eq!(format_date(19_782), "2024-02-29");
+        assert_eq!(format_date(19_783), "2024-03-01");
+        assert_eq!(format_date(47_540), "2100-02-28");
+        assert_eq!(format_date(47_541), "2100-03-01");
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        for text in ["", "2024", "2024-02", "2024-13-01", "2024-00-10", "2024-02-00", "2024-02-32", "1969-12-31", "2024-ab-01", "2023-02-29", "2024-04-31"] {
+            assert_eq!(parse_date(text), None, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use crate::models::{GameModesEnum, GameState, ZEN_MAX_TICKS, ZEN_PENALTY};
+
+pub fn is_zen(game_state: &GameState) -> bool {
+    game_state.mode == GameModesEnum::Zen
+}
+
+// Running into a wall or a solid edge keeps the snake where it is until the
+// player picks a new direction.
+pub fn bump(game_state: &mut GameState) {
+    game_state.score = game_state.score.saturating_sub(ZEN_PENALTY);
+    game_state.freeze = true;
+}
+
+// Cuts the snake off at the cell that was hit, keeping at least the head. The
+// step that ran into the body does not pop the tail as well.
+pub fn take_hit(game_state: &mut GameState, index: usize) {
+    game_state.score = game_state.score.saturating_sub(ZEN_PENALTY);
+    game_state.snake_body.truncate(index.max(1));
+    game_state.pending_growth = 0;
+}
+
+pub fn change_speed(game_state: &mut GameState, is_faster: bool) {
+    game_state.required_ticks = if is_faster {
+        game_state.required_ticks.saturating_sub(1).max(1)
+    } else {
+        (game_state.required_ticks + 1).min(ZEN_MAX_TICKS)
+    };
+}
+
+pub fn get_speed(game_state: &GameState) -> u64 {
+    ZEN_MAX_TICKS + 1 - game_state.required_ticks.min(ZEN_MAX_TICKS)
+}